@@ -1,5 +1,33 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use sqlparse::{FormatOption, Formatter};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use sqlparse::{FormatOption, Formatter, Parser};
+
+// bytes allocated and not freed yet
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATED.fetch_add(new_size, Ordering::Relaxed);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
 
 fn simple_query(c: &mut Criterion) {
     let sql = "SELECT * FROM my_table WHERE id = 1";
@@ -98,10 +126,62 @@ VALUES
     });
 }
 
+// tokenizing time should grow linearly with the input size
+fn large_input(c: &mut Criterion) {
+    const STMT: &str = "INSERT INTO user_data (first_name, last_name, address, phone) VALUES ('FIRST_NAME', 'LAST_NAME', 'SOME_ADDRESS', '9999999999');
+SELECT t1.id, t1.name, count(*) AS n FROM my_table t1 LEFT JOIN other_table t2 ON t1.id = t2.other_id WHERE t2.order BETWEEN 17 AND 30 GROUP BY t1.id, t1.name ORDER BY n DESC;
+";
+    let p = Parser::default();
+    let mut group = c.benchmark_group("large input");
+    group.sample_size(10);
+    for mb in [1, 10] {
+        let size = mb * 1024 * 1024;
+        let sql = STMT.repeat(size / STMT.len() + 1);
+        group.throughput(Throughput::Bytes(sql.len() as u64));
+        group.bench_with_input(BenchmarkId::new("tokenize", format!("{}MB", mb)), &sql, |b, sql| {
+            b.iter(|| p.parse_no_grouping(black_box(sql)))
+        });
+        group.bench_with_input(BenchmarkId::new("parse_multi", format!("{}MB", mb)), &sql, |b, sql| {
+            b.iter(|| p.parse_multi(black_box(sql)))
+        });
+        let comments = "select ".to_string() + &"/*x ".repeat(size / 4);
+        group.bench_with_input(BenchmarkId::new("unclosed comments", format!("{}MB", mb)), &comments, |b, sql| {
+            b.iter(|| p.parse_no_grouping(black_box(sql)))
+        });
+    }
+    group.finish();
+}
+
+// memory held by the parsed tokens should stay a small multiple of the input size
+fn held_memory(_c: &mut Criterion) {
+    const MAX_BYTES_PER_INPUT_BYTE: usize = 128;
+    let mut nested = String::new();
+    while nested.len() < 500_000 {
+        let mut sql = String::from("select a, b from t0 where c = 1");
+        for i in 1..12 {
+            sql = format!("select a, b, f(c) from ({}) t{} where x > {}", sql, i, i);
+        }
+        nested.push_str(&sql);
+        nested.push_str(";\n");
+    }
+    let flat = "select a, b from t where c = 1;\n".repeat(20000);
+    let p = Parser::default();
+    for (name, sql) in [("nested subqueries", &nested), ("flat statements", &flat)] {
+        let before = ALLOCATED.load(Ordering::Relaxed);
+        let stmts = p.parse_multi(sql);
+        let held = ALLOCATED.load(Ordering::Relaxed) - before;
+        println!("held memory/{}: {} bytes for {} input bytes", name, held, sql.len());
+        assert!(held <= sql.len() * MAX_BYTES_PER_INPUT_BYTE, "{} holds {} bytes for {} input bytes", name, held, sql.len());
+        drop(stmts);
+    }
+}
+
 criterion_group!(
     benches,
     simple_query,
     complex_query,
     multiple_statements_query,
+    large_input,
+    held_memory,
 );
 criterion_main!(benches);
\ No newline at end of file
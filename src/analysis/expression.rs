@@ -0,0 +1,489 @@
+use std::fmt;
+use crate::lexer::Token;
+use crate::tokens::TokenType;
+use super::is_skipped;
+
+/// operator of a binary expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BinaryOperator {
+    Or,
+    And,
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    Is,
+    IsNot,
+    IsDistinctFrom,
+    IsNotDistinctFrom,
+    Plus,
+    Minus,
+    Concat,
+    Multiply,
+    Divide,
+    Modulo,
+    /// any other operator, by its text
+    Other(String),
+}
+
+impl BinaryOperator {
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            BinaryOperator::Or => "OR",
+            BinaryOperator::And => "AND",
+            BinaryOperator::Eq => "=",
+            BinaryOperator::NotEq => "<>",
+            BinaryOperator::Lt => "<",
+            BinaryOperator::LtEq => "<=",
+            BinaryOperator::Gt => ">",
+            BinaryOperator::GtEq => ">=",
+            BinaryOperator::Is => "IS",
+            BinaryOperator::IsNot => "IS NOT",
+            BinaryOperator::IsDistinctFrom => "IS DISTINCT FROM",
+            BinaryOperator::IsNotDistinctFrom => "IS NOT DISTINCT FROM",
+            BinaryOperator::Plus => "+",
+            BinaryOperator::Minus => "-",
+            BinaryOperator::Concat => "||",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "/",
+            BinaryOperator::Modulo => "%",
+            BinaryOperator::Other(op) => op,
+        }
+    }
+}
+
+/// operator of a unary expression
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOperator {
+    Not,
+    Minus,
+    Plus,
+    BitNot,
+}
+
+impl UnaryOperator {
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UnaryOperator::Not => "NOT",
+            UnaryOperator::Minus => "-",
+            UnaryOperator::Plus => "+",
+            UnaryOperator::BitNot => "~",
+        }
+    }
+}
+
+/// expression tree over grouped tokens, from the lowest precedence:
+/// OR, AND, NOT, comparisons and predicates, `+ - ||`, `* / %`, unary operators
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression<'t, 'a> {
+    /// identifier, literal, function, CASE, subquery ...
+    Token(&'t Token<'a>),
+    /// number right of a binary `-`, the lexer keeps the operator in the number token
+    /// so `a - 1` is `a` and `- 1`, `text` is the number without the sign
+    UnsignedNumber {
+        token: &'t Token<'a>,
+        text: &'t str,
+    },
+    /// expression in parenthesis
+    Nested(Box<Expression<'t, 'a>>),
+    Unary {
+        op: UnaryOperator,
+        expr: Box<Expression<'t, 'a>>,
+    },
+    Binary {
+        left: Box<Expression<'t, 'a>>,
+        op: BinaryOperator,
+        right: Box<Expression<'t, 'a>>,
+    },
+    /// `expr [NOT] BETWEEN low AND high`
+    Between {
+        expr: Box<Expression<'t, 'a>>,
+        negated: bool,
+        low: Box<Expression<'t, 'a>>,
+        high: Box<Expression<'t, 'a>>,
+    },
+    /// `expr IS [NOT] NULL`
+    IsNull {
+        expr: Box<Expression<'t, 'a>>,
+        negated: bool,
+    },
+    /// `expr [NOT] IN (a, b, ...)`
+    InList {
+        expr: Box<Expression<'t, 'a>>,
+        negated: bool,
+        list: Vec<Expression<'t, 'a>>,
+    },
+    /// `expr [NOT] IN (SELECT ...)`
+    InSubquery {
+        expr: Box<Expression<'t, 'a>>,
+        negated: bool,
+        subquery: &'t Token<'a>,
+    },
+    /// `[NOT] EXISTS (SELECT ...)`
+    Exists {
+        negated: bool,
+        subquery: &'t Token<'a>,
+    },
+    /// `expr [NOT] LIKE pattern [ESCAPE escape]`, `op` is `LIKE`, `ILIKE`, `RLIKE`, `REGEXP` or `SIMILAR TO`
+    Like {
+        expr: Box<Expression<'t, 'a>>,
+        negated: bool,
+        op: String,
+        pattern: Box<Expression<'t, 'a>>,
+        escape: Option<Box<Expression<'t, 'a>>>,
+    },
+}
+
+impl<'t, 'a> Expression<'t, 'a> {
+
+    /// parse tokens into an expression, `None` if the tokens are not a single expression
+    pub fn parse(tokens: &'t [Token<'a>]) -> Option<Self> {
+        parse_tokens(tokens.iter())
+    }
+
+    /// condition of a WHERE, HAVING or JOIN ... ON clause group
+    pub fn from_clause(clause: &'t Token<'a>) -> Option<Self> {
+        match clause.typ {
+            TokenType::Where | TokenType::Having => {
+                let tokens = &clause.children.tokens;
+                let start = tokens.iter().position(|t| t.is_keyword())?;
+                // WHERE of the last statement ends with the semicolon
+                let end = tokens.iter().rposition(|t| !t.is_whitespace() && t.value() != ";").map(|i| i+1)?;
+                Expression::parse(tokens.get(start+1..end)?)
+            },
+            TokenType::Join => clause.join_condition().and_then(Expression::parse),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Expression<'_, '_> {
+
+    // every operation is in parenthesis to show the structure of the tree
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let not = |negated: &bool| if *negated { "NOT " } else { "" };
+        match self {
+            Expression::Token(token) => f.write_str(token.value()),
+            Expression::UnsignedNumber { text, .. } => f.write_str(text),
+            Expression::Nested(expr) => write!(f, "{}", expr),
+            Expression::Unary { op: UnaryOperator::Not, expr } => write!(f, "(NOT {})", expr),
+            Expression::Unary { op, expr } => write!(f, "({}{})", op.as_str(), expr),
+            Expression::Binary { left, op, right } => write!(f, "({} {} {})", left, op.as_str(), right),
+            Expression::Between { expr, negated, low, high } => write!(f, "({} {}BETWEEN {} AND {})", expr, not(negated), low, high),
+            Expression::IsNull { expr, negated } => write!(f, "({} IS {}NULL)", expr, not(negated)),
+            Expression::InList { expr, negated, list } => {
+                let list = list.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(", ");
+                write!(f, "({} {}IN ({}))", expr, not(negated), list)
+            },
+            Expression::InSubquery { expr, negated, subquery } => write!(f, "({} {}IN {})", expr, not(negated), subquery.value()),
+            Expression::Exists { negated, subquery } => write!(f, "({}EXISTS {})", not(negated), subquery.value()),
+            Expression::Like { expr, negated, op, pattern, escape } => {
+                write!(f, "({} {}{} {}", expr, not(negated), op, pattern)?;
+                if let Some(escape) = escape {
+                    write!(f, " ESCAPE {}", escape)?;
+                }
+                f.write_str(")")
+            },
+        }
+    }
+}
+
+fn parse_tokens<'t, 'a, I>(tokens: I) -> Option<Expression<'t, 'a>> where I: Iterator<Item = &'t Token<'a>>, 'a: 't {
+    let mut leaves = vec![];
+    tokens.for_each(|t| flatten(t, &mut leaves));
+    let mut parser = ExpressionParser { tokens: leaves, pos: 0 };
+    let expr = parser.or()?;
+    if parser.pos < parser.tokens.len() { None } else { Some(expr) }
+}
+
+// the grouping of comparisons and operations ignores precedence, their tokens are parsed again
+fn flatten<'t, 'a>(token: &'t Token<'a>, leaves: &mut Vec<&'t Token<'a>>) {
+    match token.typ {
+        TokenType::Comparison | TokenType::Operation => token.children.tokens.iter().for_each(|t| flatten(t, leaves)),
+        _ if is_skipped(token) => {},
+        _ => leaves.push(token),
+    }
+}
+
+fn keyword(token: &Token) -> String {
+    token.value().to_uppercase().split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+struct ExpressionParser<'t, 'a> {
+    tokens: Vec<&'t Token<'a>>,
+    pos: usize,
+}
+
+impl<'t, 'a> ExpressionParser<'t, 'a> {
+
+    fn peek(&self) -> Option<&'t Token<'a>> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn peek_keyword(&self) -> Option<String> {
+        self.peek().filter(|t| t.is_keyword()).map(keyword)
+    }
+
+    fn next(&mut self) -> Option<&'t Token<'a>> {
+        let token = self.peek();
+        self.pos += 1;
+        token
+    }
+
+    fn accept(&mut self, kw: &str) -> bool {
+        if self.peek_keyword().map(|k| k == kw).unwrap_or(false) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn or(&mut self) -> Option<Expression<'t, 'a>> {
+        let mut left = self.and()?;
+        while self.accept("OR") {
+            let right = self.and()?;
+            left = binary(left, BinaryOperator::Or, right);
+        }
+        Some(left)
+    }
+
+    fn and(&mut self) -> Option<Expression<'t, 'a>> {
+        let mut left = self.not()?;
+        while self.accept("AND") {
+            let right = self.not()?;
+            left = binary(left, BinaryOperator::And, right);
+        }
+        Some(left)
+    }
+
+    fn not(&mut self) -> Option<Expression<'t, 'a>> {
+        let is_exists = self.tokens.get(self.pos+1).map(|t| t.is_keyword() && keyword(t) == "EXISTS").unwrap_or(false);
+        if !is_exists && self.accept("NOT") {
+            let expr = self.not()?;
+            return Some(Expression::Unary { op: UnaryOperator::Not, expr: Box::new(expr) })
+        }
+        self.predicate()
+    }
+
+    fn predicate(&mut self) -> Option<Expression<'t, 'a>> {
+        let mut left = self.additive()?;
+        while let Some(token) = self.peek() {
+            left = match token.typ {
+                TokenType::OperatorComparison => {
+                    self.pos += 1;
+                    self.comparison(left, &keyword(token))?
+                },
+                _ if token.is_keyword() => match keyword(token).as_str() {
+                    "IS" => {
+                        self.pos += 1;
+                        self.is(left)?
+                    },
+                    "NOT NULL" => {
+                        self.pos += 1;
+                        Expression::IsNull { expr: Box::new(left), negated: true }
+                    },
+                    "NOT" if self.tokens.get(self.pos+1).map(|t| t.is_keyword() || t.typ == TokenType::OperatorComparison).unwrap_or(false) => {
+                        self.pos += 1;
+                        let op = keyword(self.next()?);
+                        self.comparison(left, &format!("NOT {}", op))?
+                    },
+                    "BETWEEN" | "IN" | "LIKE" | "ILIKE" | "RLIKE" | "REGEXP" | "SIMILAR TO" => {
+                        self.pos += 1;
+                        self.comparison(left, &keyword(token))?
+                    },
+                    _ => break,
+                },
+                _ => break,
+            }
+        }
+        Some(left)
+    }
+
+    // the operator is consumed, `op` is normalized
+    fn comparison(&mut self, left: Expression<'t, 'a>, op: &str) -> Option<Expression<'t, 'a>> {
+        let (negated, op) = match op.strip_prefix("NOT ") {
+            Some(op) => (true, op),
+            None => (false, op),
+        };
+        let expr = Box::new(left);
+        match op {
+            "BETWEEN" => {
+                let low = Box::new(self.additive()?);
+                if !self.accept("AND") {
+                    return None
+                }
+                let high = Box::new(self.additive()?);
+                Some(Expression::Between { expr, negated, low, high })
+            },
+            "IN" => {
+                let token = self.next()?;
+                match token.typ {
+                    TokenType::Subquery => Some(Expression::InSubquery { expr, negated, subquery: token }),
+                    TokenType::Parenthesis => Some(Expression::InList { expr, negated, list: items(token)? }),
+                    _ => None,
+                }
+            },
+            "LIKE" | "ILIKE" | "RLIKE" | "REGEXP" | "SIMILAR TO" => {
+                let pattern = Box::new(self.additive()?);
+                let escape = if self.accept("ESCAPE") { Some(Box::new(self.additive()?)) } else { None };
+                Some(Expression::Like { expr, negated, op: op.to_string(), pattern, escape })
+            },
+            _ if negated => None,
+            _ => {
+                let op = match op {
+                    "=" | "==" => BinaryOperator::Eq,
+                    "<>" | "!=" => BinaryOperator::NotEq,
+                    "<" => BinaryOperator::Lt,
+                    "<=" => BinaryOperator::LtEq,
+                    ">" => BinaryOperator::Gt,
+                    ">=" => BinaryOperator::GtEq,
+                    _ => BinaryOperator::Other(op.to_string()),
+                };
+                let right = self.additive()?;
+                Some(Expression::Binary { left: expr, op, right: Box::new(right) })
+            },
+        }
+    }
+
+    // IS [NOT] NULL, IS [NOT] DISTINCT FROM expr, IS [NOT] TRUE ...
+    fn is(&mut self, left: Expression<'t, 'a>) -> Option<Expression<'t, 'a>> {
+        let expr = Box::new(left);
+        if self.accept("NOT NULL") {
+            return Some(Expression::IsNull { expr, negated: true })
+        }
+        let negated = self.accept("NOT");
+        if self.accept("NULL") {
+            return Some(Expression::IsNull { expr, negated })
+        }
+        let op = if self.accept("DISTINCT") {
+            if !self.accept("FROM") {
+                return None
+            }
+            if negated { BinaryOperator::IsNotDistinctFrom } else { BinaryOperator::IsDistinctFrom }
+        } else if negated { BinaryOperator::IsNot } else { BinaryOperator::Is };
+        let right = self.additive()?;
+        Some(Expression::Binary { left: expr, op, right: Box::new(right) })
+    }
+
+    fn additive(&mut self) -> Option<Expression<'t, 'a>> {
+        let mut left = self.multiplicative()?;
+        while let Some(token) = self.peek() {
+            // `a - 1` is `a` and the number `- 1` to the lexer, the sign is the operator
+            if is_signed_number(token) {
+                let op = if token.value().starts_with('-') { BinaryOperator::Minus } else { BinaryOperator::Plus };
+                let text = token.value()[1..].trim_start();
+                self.pos += 1;
+                let right = self.multiplicative_rest(Expression::UnsignedNumber { token, text })?;
+                left = binary(left, op, right);
+                continue
+            }
+            if token.typ != TokenType::Operator {
+                break
+            }
+            let op = match token.value() {
+                "+" => BinaryOperator::Plus,
+                "-" => BinaryOperator::Minus,
+                "||" => BinaryOperator::Concat,
+                "*" | "/" | "%" => break,
+                op => BinaryOperator::Other(op.to_string()),
+            };
+            self.pos += 1;
+            let right = self.multiplicative()?;
+            left = binary(left, op, right);
+        }
+        Some(left)
+    }
+
+    fn multiplicative(&mut self) -> Option<Expression<'t, 'a>> {
+        let left = self.unary()?;
+        self.multiplicative_rest(left)
+    }
+
+    // operations after the first operand
+    fn multiplicative_rest(&mut self, mut left: Expression<'t, 'a>) -> Option<Expression<'t, 'a>> {
+        // `*` is a wildcard to the lexer
+        while let Some(token) = self.peek().filter(|t| matches!(t.typ, TokenType::Operator | TokenType::Wildcard)) {
+            let op = match token.value() {
+                "*" => BinaryOperator::Multiply,
+                "/" => BinaryOperator::Divide,
+                "%" => BinaryOperator::Modulo,
+                _ => break,
+            };
+            self.pos += 1;
+            let right = self.unary()?;
+            left = binary(left, op, right);
+        }
+        Some(left)
+    }
+
+    fn unary(&mut self) -> Option<Expression<'t, 'a>> {
+        let token = self.peek()?;
+        if token.typ == TokenType::Operator {
+            let op = match token.value() {
+                "-" => UnaryOperator::Minus,
+                "+" => UnaryOperator::Plus,
+                "~" => UnaryOperator::BitNot,
+                _ => return None,
+            };
+            self.pos += 1;
+            let expr = self.unary()?;
+            return Some(Expression::Unary { op, expr: Box::new(expr) })
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Option<Expression<'t, 'a>> {
+        let token = self.next()?;
+        match token.typ {
+            TokenType::Keyword if keyword(token) == "NOT" => {
+                if !self.accept("EXISTS") {
+                    return None
+                }
+                let subquery = self.next().filter(|t| t.typ == TokenType::Subquery)?;
+                Some(Expression::Exists { negated: true, subquery })
+            },
+            TokenType::Keyword if keyword(token) == "EXISTS" => {
+                let subquery = self.next().filter(|t| t.typ == TokenType::Subquery)?;
+                Some(Expression::Exists { negated: false, subquery })
+            },
+            TokenType::Keyword if matches!(keyword(token).as_str(), "AND" | "OR" | "IS" | "BETWEEN" | "IN" | "ESCAPE") => None,
+            TokenType::Parenthesis => {
+                let inner = &token.children.tokens;
+                // rows like `(a, b)` are kept as tokens
+                if inner.iter().any(|t| t.typ == TokenType::IdentifierList || t.value() == ",") {
+                    return Some(Expression::Token(token))
+                }
+                let inner = inner.get(1..inner.len().saturating_sub(1)).filter(|t| !t.is_empty())?;
+                Expression::parse(inner).map(|expr| Expression::Nested(Box::new(expr)))
+            },
+            TokenType::Operator | TokenType::OperatorComparison | TokenType::Punctuation => None,
+            _ => Some(Expression::Token(token)),
+        }
+    }
+}
+
+fn is_signed_number(token: &Token) -> bool {
+    matches!(token.typ, TokenType::NumberInteger | TokenType::NumberFloat) && token.value().starts_with(['-', '+'])
+}
+
+fn binary<'t, 'a>(left: Expression<'t, 'a>, op: BinaryOperator, right: Expression<'t, 'a>) -> Expression<'t, 'a> {
+    Expression::Binary { left: Box::new(left), op, right: Box::new(right) }
+}
+
+// items of the list of `IN (...)`
+fn items<'t, 'a>(parenthesis: &'t Token<'a>) -> Option<Vec<Expression<'t, 'a>>> {
+    let inner = &parenthesis.children.tokens;
+    let inner = inner.get(1..inner.len().saturating_sub(1))?;
+    let tokens = inner.iter()
+        .flat_map(|t| if t.typ == TokenType::IdentifierList { t.children.tokens.iter().collect() } else { vec![t] })
+        .collect::<Vec<_>>();
+    tokens.split(|t| t.typ == TokenType::Punctuation && t.value() == ",")
+        .filter(|item| item.iter().any(|t| !is_skipped(t)))
+        .map(|item| parse_tokens(item.iter().copied()))
+        .collect()
+}
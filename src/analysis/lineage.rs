@@ -0,0 +1,415 @@
+use std::fmt;
+use crate::lexer::Token;
+use crate::tokens::TokenType;
+use super::{is_skipped, clause_tokens, name_parts};
+
+/// a column of a table, `table` is the qualified name of the table if it is known
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ColumnRef {
+    pub table: Option<String>,
+    pub column: String,
+}
+
+impl fmt::Display for ColumnRef {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.table {
+            Some(table) => write!(f, "{}.{}", table, self.column),
+            None => f.write_str(&self.column),
+        }
+    }
+}
+
+/// source columns of an output column of a query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnLineage {
+    /// alias of the output column, its column name or the text of its expression
+    pub name: String,
+    /// the column inserted by `INSERT ... SELECT`
+    pub target: Option<ColumnRef>,
+    /// columns of tables the output column is derived from, `*` if the columns are unknown
+    pub sources: Vec<ColumnRef>,
+}
+
+/// lineage of the output columns of a `SELECT` or of the columns inserted by `INSERT ... SELECT`,
+/// aliases, common table expressions and subqueries are followed to the columns of tables
+pub fn column_lineage(tokens: &[Token]) -> Vec<ColumnLineage> {
+    let mut lineage = Lineage { ctes: vec![] };
+    lineage.statement(tokens)
+}
+
+// a table or a subquery in FROM, by its alias or name
+enum Source {
+    Table(String),
+    Derived(Vec<ColumnLineage>),
+}
+
+struct Lineage {
+    ctes: Vec<(String, Vec<ColumnLineage>)>,
+}
+
+impl Lineage {
+
+    fn statement(&mut self, tokens: &[Token]) -> Vec<ColumnLineage> {
+        let tokens = self.with(tokens);
+        match tokens.iter().find(|t| !is_skipped(t)) {
+            Some(t) if t.typ == TokenType::KeywordDML && t.normalized() == "INSERT" => self.insert(tokens),
+            Some(_) => self.query(tokens),
+            None => vec![],
+        }
+    }
+
+    // define the common table expressions of a leading WITH, the tokens after them are returned
+    fn with<'t, 'a>(&mut self, tokens: &'t [Token<'a>]) -> &'t [Token<'a>] {
+        let idx = match tokens.iter().position(|t| !is_skipped(t)) {
+            Some(idx) if tokens[idx].typ == TokenType::With => idx,
+            _ => return tokens,
+        };
+        for cte in tokens[idx].get_ctes() {
+            let (name, body) = match (cte.get_real_name(), cte.get_cte_body()) {
+                (Some(name), Some(body)) => (name.to_string(), body),
+                _ => continue,
+            };
+            let mut columns = self.query(inner(body));
+            columns.iter_mut().zip(cte.get_cte_columns()).for_each(|(column, name)| column.name = name.to_string());
+            self.ctes.push((name, columns));
+        }
+        &tokens[idx+1..]
+    }
+
+    // `INSERT INTO table [(columns)] query`, query columns are inserted by position
+    fn insert(&mut self, tokens: &[Token]) -> Vec<ColumnLineage> {
+        let into = tokens.iter().position(|t| t.normalized() == "INTO");
+        let target = into.and_then(|idx| tokens[idx+1..].iter().position(|t| !is_skipped(t)).map(|i| idx+1+i));
+        let target = match target {
+            Some(idx) => idx,
+            None => return vec![],
+        };
+        let table = name_parts(&tokens[target], true).map(|parts| parts.join("."));
+        let names = find_parenthesis(&tokens[target]).map(column_names).unwrap_or_default();
+        let rest = &tokens[target+1..];
+        let query = match rest.iter().position(|t| is_query_start(t)) {
+            Some(idx) => &rest[idx..],
+            None => return vec![],
+        };
+        let mut columns = self.statement(query);
+        for (i, column) in columns.iter_mut().enumerate() {
+            if let Some(name) = names.get(i) {
+                column.name = name.clone();
+            }
+            column.target = Some(ColumnRef { table: table.clone(), column: column.name.clone() });
+        }
+        columns
+    }
+
+    // selects combined by UNION, INTERSECT and EXCEPT, the sources of a column are merged by position
+    fn query(&mut self, tokens: &[Token]) -> Vec<ColumnLineage> {
+        let tokens = self.with(tokens);
+        let first = tokens.iter().find(|t| !is_skipped(t));
+        if let Some(first) = first.filter(|t| t.typ == TokenType::Subquery) {
+            return self.query(inner(first))
+        }
+        let mut columns: Vec<ColumnLineage> = vec![];
+        for select in tokens.split(|t| is_set_operator(t)) {
+            let branch = match select.iter().find(|t| !is_skipped(t)) {
+                Some(t) if t.typ == TokenType::Subquery => self.query(inner(t)),
+                _ => self.select(select),
+            };
+            if columns.is_empty() {
+                columns = branch;
+                continue
+            }
+            for (column, other) in columns.iter_mut().zip(branch) {
+                for source in other.sources {
+                    push_unique(&mut column.sources, source);
+                }
+            }
+        }
+        columns
+    }
+
+    fn select(&mut self, tokens: &[Token]) -> Vec<ColumnLineage> {
+        let scope = self.scope(tokens);
+        let mut columns = vec![];
+        for item in projection(tokens) {
+            let (expr, alias) = split_alias(item);
+            // `*` and `t.*` are replaced by the columns of subqueries, or by `t.*` for tables
+            if let Some(qualifier) = wildcard(&expr) {
+                for (key, source) in &scope {
+                    if qualifier.as_ref().map(|q| !q.eq_ignore_ascii_case(key)).unwrap_or(false) {
+                        continue
+                    }
+                    match source {
+                        Source::Table(table) => columns.push(ColumnLineage {
+                            name: "*".to_string(),
+                            target: None,
+                            sources: vec![ColumnRef { table: Some(table.clone()), column: "*".to_string() }],
+                        }),
+                        Source::Derived(derived) => columns.extend(derived.iter().cloned()),
+                    }
+                }
+                continue
+            }
+            let mut sources = vec![];
+            self.expression(&expr, &scope, &mut sources);
+            let name = alias.unwrap_or_else(|| match dotted(&expr) {
+                Some((mut parts, len)) if len == expr.len() => parts.pop().unwrap_or_default(),
+                _ => expr.iter().map(|t| t.value()).collect::<String>(),
+            });
+            columns.push(ColumnLineage { name, target: None, sources });
+        }
+        columns
+    }
+
+    // tables and subqueries after FROM and JOIN
+    fn scope(&mut self, tokens: &[Token]) -> Vec<(String, Source)> {
+        let mut scope = vec![];
+        let mut expect = false;
+        for token in clause_tokens(tokens) {
+            let word = if token.is_keyword() { token.normalized().to_string() } else { String::new() };
+            if expect {
+                match token.typ {
+                    TokenType::Identifier => self.source(token, &mut scope),
+                    TokenType::IdentifierList => token.children.tokens.iter()
+                        .filter(|t| t.typ == TokenType::Identifier)
+                        .for_each(|t| self.source(t, &mut scope)),
+                    _ if word == "ONLY" || word == "LATERAL" => continue,
+                    _ => {},
+                }
+                expect = false;
+            }
+            if word == "FROM" || word.ends_with("JOIN") {
+                expect = true;
+            }
+        }
+        scope
+    }
+
+    fn source(&mut self, token: &Token, scope: &mut Vec<(String, Source)>) {
+        let alias = token.get_alias().map(|alias| alias.into_owned());
+        let first = token.children.tokens.iter().find(|t| !is_skipped(t));
+        if let Some(query) = first.filter(|t| t.typ == TokenType::Subquery) {
+            let columns = self.query(inner(query));
+            scope.push((alias.unwrap_or_default(), Source::Derived(columns)));
+            return
+        }
+        let parts = match name_parts(token, false) {
+            Some(parts) => parts,
+            None => return,
+        };
+        let name = parts.last().cloned().unwrap_or_default();
+        let cte = self.ctes.iter().rev().find(|(cte, _)| parts.len() == 1 && cte.eq_ignore_ascii_case(&name));
+        let source = match cte {
+            Some((_, columns)) => Source::Derived(columns.clone()),
+            None => Source::Table(parts.join(".")),
+        };
+        scope.push((alias.unwrap_or(name), source));
+    }
+
+    // columns referenced by the tokens of an expression
+    fn expression(&mut self, tokens: &[&Token], scope: &[(String, Source)], sources: &mut Vec<ColumnRef>) {
+        let mut i = 0;
+        while i < tokens.len() {
+            if let Some((parts, len)) = dotted(&tokens[i..]) {
+                // the name of a function is not a column, calls in subqueries are not always grouped
+                let is_call = tokens.get(i+len).map(|t| t.typ == TokenType::Parenthesis).unwrap_or(false);
+                if !is_call {
+                    resolve(&parts, scope, sources);
+                }
+                i += len;
+                continue
+            }
+            let token = tokens[i];
+            match token.typ {
+                TokenType::Function => {
+                    let args = token.children.tokens.iter().skip(1).filter(|t| !is_skipped(t)).collect::<Vec<_>>();
+                    self.expression(&args, scope, sources);
+                },
+                TokenType::Subquery => {
+                    for column in self.query(inner(token)) {
+                        column.sources.into_iter().for_each(|source| push_unique(sources, source));
+                    }
+                },
+                _ if token.is_group() => {
+                    let children = token.children.tokens.iter().filter(|t| !is_skipped(t)).collect::<Vec<_>>();
+                    self.expression(&children, scope, sources);
+                },
+                _ => {},
+            }
+            i += 1;
+        }
+    }
+}
+
+// resolve `column`, `alias.column` or `table.column` by the tables and subqueries in FROM
+fn resolve(parts: &[String], scope: &[(String, Source)], sources: &mut Vec<ColumnRef>) {
+    let (column, qualifier) = match parts.split_last() {
+        Some((column, qualifier)) => (column, qualifier.join(".")),
+        None => return,
+    };
+    let source = if qualifier.is_empty() {
+        match scope {
+            [(_, source)] => Some(source),
+            _ => scope.iter().map(|(_, source)| source).find(|source| match source {
+                Source::Derived(columns) => columns.iter().any(|c| c.name.eq_ignore_ascii_case(column)),
+                Source::Table(_) => false,
+            }),
+        }
+    } else {
+        scope.iter().find(|(key, source)| key.eq_ignore_ascii_case(&qualifier) || match source {
+            Source::Table(table) => table.eq_ignore_ascii_case(&qualifier),
+            Source::Derived(_) => false,
+        }).map(|(_, source)| source)
+    };
+    match source {
+        Some(Source::Table(table)) => push_unique(sources, ColumnRef { table: Some(table.clone()), column: column.clone() }),
+        Some(Source::Derived(columns)) => {
+            let derived = columns.iter().find(|c| c.name.eq_ignore_ascii_case(column));
+            derived.into_iter().flat_map(|c| c.sources.iter().cloned()).for_each(|source| push_unique(sources, source));
+        },
+        None => {
+            let table = if qualifier.is_empty() { None } else { Some(qualifier) };
+            push_unique(sources, ColumnRef { table, column: column.clone() });
+        },
+    }
+}
+
+fn push_unique(sources: &mut Vec<ColumnRef>, source: ColumnRef) {
+    if !sources.contains(&source) {
+        sources.push(source);
+    }
+}
+
+// tokens inside the parentheses
+fn inner<'t, 'a>(token: &'t Token<'a>) -> &'t [Token<'a>] {
+    let tokens = &token.children.tokens;
+    let start = if tokens.first().map(|t| t.value() == "(").unwrap_or(false) { 1 } else { 0 };
+    let end = if tokens.len() > start && tokens.last().map(|t| t.value() == ")").unwrap_or(false) { tokens.len() - 1 } else { tokens.len() };
+    &tokens[start..end]
+}
+
+fn is_query_start(token: &Token) -> bool {
+    match token.typ {
+        TokenType::KeywordDML => token.normalized() == "SELECT",
+        TokenType::With | TokenType::Subquery => true,
+        _ => false,
+    }
+}
+
+fn is_set_operator(token: &Token) -> bool {
+    token.typ == TokenType::Keyword && ["UNION", "INTERSECT", "EXCEPT", "MINUS"].iter().any(|w| token.normalized().starts_with(w))
+}
+
+// a plain column name, `column` or `table.column`
+fn is_column_ref(token: &Token) -> bool {
+    match token.typ {
+        TokenType::Name | TokenType::StringSymbol => true,
+        TokenType::Identifier => token.children.tokens.iter().all(|t| {
+            (t.typ == TokenType::Punctuation && t.value() == ".") || is_column_ref(t)
+        }),
+        _ => false,
+    }
+}
+
+// parts and token count of a column name at the start of tokens, the parts of `t.c` may be split
+// into `t`, `.` and `c`
+fn dotted(tokens: &[&Token]) -> Option<(Vec<String>, usize)> {
+    let mut parts = vec![];
+    let mut len = 0;
+    while let Some(token) = tokens.get(len).filter(|t| is_column_ref(t)) {
+        parts.extend(name_parts(token, false)?);
+        len += 1;
+        match tokens.get(len) {
+            Some(dot) if dot.typ == TokenType::Punctuation && dot.value() == "." && tokens.get(len+1).map(|t| is_column_ref(t)).unwrap_or(false) => len += 1,
+            _ => break,
+        }
+    }
+    if len > 0 { Some((parts, len)) } else { None }
+}
+
+// the parenthesis of `table (columns)` or `name(columns)`
+fn find_parenthesis<'t, 'a>(token: &'t Token<'a>) -> Option<&'t Token<'a>> {
+    token.children.tokens.iter().rev().find_map(|t| match t.typ {
+        TokenType::Parenthesis => Some(t),
+        TokenType::Function | TokenType::Identifier => find_parenthesis(t),
+        _ => None,
+    })
+}
+
+fn column_names(parenthesis: &Token) -> Vec<String> {
+    flatten(inner(parenthesis)).split(|t| t.value() == ",")
+        .filter_map(|item| item.first().and_then(|t| name_parts(t, false)).and_then(|mut parts| parts.pop()))
+        .collect()
+}
+
+// significant tokens, identifier lists are flattened so that items are separated by commas
+fn flatten<'t, 'a>(tokens: &'t [Token<'a>]) -> Vec<&'t Token<'a>> {
+    let mut flat = vec![];
+    for token in tokens.iter().filter(|t| !is_skipped(t)) {
+        if token.typ == TokenType::IdentifierList {
+            flat.extend(flatten(&token.children.tokens));
+        } else {
+            flat.push(token);
+        }
+    }
+    flat
+}
+
+// items of the select list, from SELECT to FROM, INTO or WHERE
+fn projection<'t, 'a>(tokens: &'t [Token<'a>]) -> Vec<Vec<&'t Token<'a>>> {
+    let start = match tokens.iter().position(|t| t.typ == TokenType::KeywordDML && t.normalized() == "SELECT") {
+        Some(idx) => idx + 1,
+        None => return vec![],
+    };
+    let end = tokens[start..].iter()
+        .position(|t| t.typ == TokenType::Where || t.typ.is_clause() || matches!(t.normalized(), "FROM" | "INTO"))
+        .map(|idx| start + idx)
+        .unwrap_or(tokens.len());
+    let items = flatten(&tokens[start..end]).into_iter()
+        .skip_while(|t| t.typ == TokenType::Keyword && matches!(t.normalized(), "DISTINCT" | "ALL"))
+        .collect::<Vec<_>>();
+    items.split(|t| t.typ == TokenType::Punctuation && t.value() == ",").map(|item| item.to_vec()).filter(|item| !item.is_empty()).collect()
+}
+
+// the expression of an item and its alias, `expr AS alias`, `expr alias` or an aliased identifier
+fn split_alias<'t, 'a>(item: Vec<&'t Token<'a>>) -> (Vec<&'t Token<'a>>, Option<String>) {
+    if let [token] = item.as_slice() {
+        if let Some(alias) = token.get_alias() {
+            let alias = alias.into_owned();
+            let children = token.children.tokens.iter().filter(|t| !is_skipped(t)).collect::<Vec<_>>();
+            let end = children.iter().position(|t| t.typ == TokenType::Keyword && t.normalized() == "AS")
+                .unwrap_or(children.len().saturating_sub(1));
+            return (children[..end].to_vec(), Some(alias))
+        }
+        return (item, None)
+    }
+    let n = item.len();
+    let last_is_name = item.last().map(|t| is_column_ref(t) && name_parts(t, false).map(|p| p.len() == 1).unwrap_or(false)).unwrap_or(false);
+    if n >= 3 && last_is_name && item[n-2].typ == TokenType::Keyword && item[n-2].normalized() == "AS" {
+        let alias = name_parts(item[n-1], false).and_then(|mut parts| parts.pop());
+        return (item[..n-2].to_vec(), alias)
+    }
+    if n >= 2 && last_is_name && !matches!(item[n-2].typ, TokenType::Operator | TokenType::OperatorComparison | TokenType::Keyword) {
+        let alias = name_parts(item[n-1], false).and_then(|mut parts| parts.pop());
+        return (item[..n-1].to_vec(), alias)
+    }
+    (item, None)
+}
+
+// `*` has no qualifier, `t.*` has `t`
+fn wildcard(expr: &[&Token]) -> Option<Option<String>> {
+    match expr {
+        [token] if token.typ == TokenType::Wildcard => Some(None),
+        [token] if token.typ == TokenType::Identifier => {
+            let children = token.children.tokens.iter().filter(|t| !is_skipped(t)).collect::<Vec<_>>();
+            match children.split_last() {
+                Some((last, qualifier)) if last.typ == TokenType::Wildcard && !qualifier.is_empty() => {
+                    let parts = qualifier.iter().filter(|t| t.value() != ".").filter_map(|t| name_parts(t, false)).flatten().collect::<Vec<_>>();
+                    Some(Some(parts.join(".")))
+                },
+                _ => None,
+            }
+        },
+        _ => None,
+    }
+}
@@ -0,0 +1,49 @@
+pub mod tables;
+pub mod lineage;
+pub mod expression;
+
+use crate::lexer::{Token, unquote_name};
+use crate::tokens::TokenType;
+pub use tables::{TableRef, TableAccess};
+pub use lineage::{ColumnRef, ColumnLineage};
+pub use expression::{Expression, BinaryOperator, UnaryOperator};
+
+pub(crate) fn is_skipped(token: &Token) -> bool {
+    token.is_whitespace() || matches!(token.typ, TokenType::Comment | TokenType::CommentSingle | TokenType::CommentMultiline)
+}
+
+// significant tokens with the tokens of clause groups in their place
+fn clause_tokens<'t, 'a>(tokens: &'t [Token<'a>]) -> Vec<&'t Token<'a>> {
+    tokens.iter()
+        .flat_map(|t| if t.typ.is_clause() { t.children.tokens.iter().collect() } else { vec![t] })
+        .filter(|t| !is_skipped(t))
+        .collect()
+}
+
+// parts of a dotted name like `db.schema.table`, a function call ends the name if allowed
+fn name_parts(token: &Token, allow_function: bool) -> Option<Vec<String>> {
+    match token.typ {
+        TokenType::Name | TokenType::StringSymbol => return Some(vec![unquote_name(token.value()).into_owned()]),
+        TokenType::Function if allow_function => {
+            let name = token.children.tokens.iter().find(|t| !is_skipped(t))?;
+            return name_parts(name, false)
+        },
+        TokenType::Identifier => {},
+        _ => return None,
+    }
+    let mut parts = vec![];
+    for child in &token.children.tokens {
+        match child.typ {
+            TokenType::Punctuation if child.value() == "." => continue,
+            TokenType::Name | TokenType::StringSymbol | TokenType::Identifier | TokenType::Function => {
+                parts.extend(name_parts(child, allow_function)?);
+                if child.typ == TokenType::Function {
+                    break
+                }
+            },
+            _ if parts.is_empty() => return None,
+            _ => break,
+        }
+    }
+    if parts.is_empty() { None } else { Some(parts) }
+}
@@ -0,0 +1,151 @@
+use crate::lexer::Token;
+use crate::tokens::TokenType;
+use super::{clause_tokens, name_parts};
+
+/// whether a statement reads or writes a table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TableAccess {
+    /// `FROM`, `JOIN`, `USING` and subqueries
+    Read,
+    /// `INSERT INTO`, `UPDATE`, `DELETE FROM`, `MERGE INTO`, `SELECT INTO` and the target of
+    /// `CREATE`, `DROP`, `ALTER` and `TRUNCATE`
+    Write,
+}
+
+/// a table referenced by a statement
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TableRef {
+    /// qualifiers of the name, `db.schema` of `db.schema.table`
+    pub schema: Option<String>,
+    pub name: String,
+    pub alias: Option<String>,
+    pub access: TableAccess,
+}
+
+/// tables referenced by grouped tokens in order of appearance, names of common table expressions are left out
+pub fn tables(tokens: &[Token]) -> Vec<TableRef> {
+    let mut walker = TableWalker { scopes: vec![], in_args: false, tables: vec![] };
+    walker.walk(tokens);
+    walker.tables
+}
+
+// keywords allowed between a table keyword and the table
+const TABLE_PREFIXES: [&str; 7] = ["IF", "NOT", "EXISTS", "ONLY", "LATERAL", "TABLE", "RECURSIVE"];
+
+struct TableWalker {
+    // names of common table expressions visible at each nested query level
+    scopes: Vec<Vec<String>>,
+    // FROM in function arguments like `extract(year from d)` names no table
+    in_args: bool,
+    tables: Vec<TableRef>,
+}
+
+impl TableWalker {
+
+    // common table expressions defined in the tokens are visible only to them and their groups
+    fn walk(&mut self, tokens: &[Token]) {
+        self.scopes.push(vec![]);
+        self.walk_scope(tokens);
+        self.scopes.pop();
+    }
+
+    fn walk_scope(&mut self, tokens: &[Token]) {
+        let mut head: Option<String> = None;
+        let mut prev: Option<String> = None;
+        // access of the table expected next
+        let mut expect: Option<TableAccess> = None;
+        for token in clause_tokens(tokens) {
+            let word = if token.is_keyword() { token.normalized().to_string() } else { String::new() };
+            if head.is_none() {
+                head = Some(word.split(' ').next().unwrap_or("").to_string());
+            }
+            let head = head.as_deref().unwrap_or("");
+            if let Some(access) = expect {
+                match token.typ {
+                    TokenType::Identifier | TokenType::Function => {
+                        self.table(token, access);
+                        expect = None;
+                        prev = None;
+                        continue
+                    },
+                    TokenType::IdentifierList => {
+                        for child in token.children.tokens.iter().filter(|t| matches!(t.typ, TokenType::Identifier | TokenType::Function)) {
+                            self.table(child, access);
+                        }
+                        expect = None;
+                        prev = None;
+                        continue
+                    },
+                    _ if TABLE_PREFIXES.contains(&word.as_str()) => continue,
+                    _ => expect = None,
+                }
+            }
+            match token.typ {
+                TokenType::With => self.ctes(token),
+                // `a IS DISTINCT FROM b` compares values
+                _ if word == "FROM" && !self.in_args && !prev.as_deref().unwrap_or("").ends_with("DISTINCT") => expect = Some(if prev.as_deref() == Some("DELETE") { TableAccess::Write } else { TableAccess::Read }),
+                _ if word.ends_with("JOIN") || word == "USING" => expect = Some(TableAccess::Read),
+                _ if word == "INTO" || word == "UPDATE" => expect = Some(TableAccess::Write),
+                _ if word == "TRUNCATE" => expect = Some(TableAccess::Write),
+                _ if (word == "TABLE" || word == "VIEW") && matches!(head, "CREATE" | "DROP" | "ALTER" | "TRUNCATE") => expect = Some(TableAccess::Write),
+                _ if token.is_group() => self.walk_group(token),
+                _ => {},
+            }
+            prev = Some(word);
+        }
+    }
+
+    // arguments of a function, the query of a subquery starts over
+    fn walk_group(&mut self, token: &Token) {
+        let in_args = match token.typ {
+            TokenType::Function => true,
+            TokenType::Subquery => false,
+            _ => self.in_args,
+        };
+        let outer = std::mem::replace(&mut self.in_args, in_args);
+        self.walk(&token.children.tokens);
+        self.in_args = outer;
+    }
+
+    // a query of a common table expression sees the expressions before it, and all of them
+    // including itself with RECURSIVE, the query after WITH sees all of them
+    fn ctes(&mut self, with: &Token) {
+        let definitions = with.get_ctes();
+        let recursive = with.is_recursive();
+        let define = |walker: &mut Self, definition: &Token| if let Some(name) = definition.get_real_name() {
+            walker.scopes.last_mut().unwrap().push(name.into_owned());
+        };
+        if recursive {
+            definitions.iter().for_each(|definition| define(self, definition));
+        }
+        for definition in &definitions {
+            if let Some(body) = definition.get_cte_body() {
+                self.walk(&body.children.tokens);
+            }
+            if !recursive {
+                define(self, definition);
+            }
+        }
+    }
+
+    fn table(&mut self, token: &Token, access: TableAccess) {
+        // a table function or a derived table is not a table, tables of its arguments or query are
+        let parts = match name_parts(token, access == TableAccess::Write) {
+            Some(parts) => parts,
+            None => return self.walk_group(token),
+        };
+        let (name, schema) = match parts.split_last() {
+            Some((name, schema)) if !schema.is_empty() => (name.clone(), Some(schema.join("."))),
+            Some((name, _)) => (name.clone(), None),
+            None => return,
+        };
+        if schema.is_none() && self.scopes.iter().flatten().any(|cte| cte.eq_ignore_ascii_case(&name)) {
+            return
+        }
+        let alias = token.get_alias().map(|alias| alias.into_owned());
+        let table = TableRef { schema, name, alias, access };
+        if !self.tables.contains(&table) {
+            self.tables.push(table);
+        }
+    }
+}
@@ -0,0 +1,554 @@
+//! Typed view of a grouped statement
+//!
+//! Every node references the tokens it is built from, parts that are not
+//! recognized are kept as [`Raw`] nodes.
+//!
+//! ```
+//! use sqlparse::{Parser, ast};
+//!
+//! let p = Parser::default();
+//! let stmts = p.parse_statements("select a, b from t join u on t.id = u.id where a > 1 limit 10");
+//! let ast::Statement::Select(select) = stmts[0].ast() else { panic!() };
+//! assert_eq!(select.projections.len(), 2);
+//! assert_eq!(select.joins[0].relation.and_then(|t| t.get_real_name()).as_deref(), Some("u"));
+//! assert_eq!(select.limit.map(|t| t.value()), Some("10"));
+//! ```
+
+use std::borrow::Cow;
+use std::fmt;
+use crate::lexer::{Token, unquote_name};
+use crate::tokens::{TokenType, JoinKind};
+use crate::analysis::{Expression, is_skipped};
+
+/// typed view of a statement
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement<'t, 'a> {
+    Select(Box<Select<'t, 'a>>),
+    Insert(Box<Insert<'t, 'a>>),
+    Update(Box<Update<'t, 'a>>),
+    Delete(Box<Delete<'t, 'a>>),
+    CreateTable(Box<CreateTable<'t, 'a>>),
+    /// any other statement
+    Raw(Raw<'t, 'a>),
+}
+
+impl<'t, 'a> From<&'t [Token<'a>]> for Statement<'t, 'a> {
+
+    fn from(tokens: &'t [Token<'a>]) -> Self {
+        let mut significant = tokens.iter().filter(|t| !is_skipped(t));
+        let first = match significant.next() {
+            Some(t) if t.typ == TokenType::With => significant.next(),
+            first => first,
+        };
+        match first.map(|t| (t.typ.clone(), t.normalized())) {
+            Some((TokenType::KeywordDML, "SELECT")) => Statement::Select(Box::new(Select::parse(tokens))),
+            Some((TokenType::KeywordDML, "INSERT")) => Statement::Insert(Box::new(Insert::parse(tokens))),
+            Some((TokenType::KeywordDML, "UPDATE")) => Statement::Update(Box::new(Update::parse(tokens))),
+            Some((TokenType::KeywordDML, "DELETE")) => Statement::Delete(Box::new(Delete::parse(tokens))),
+            Some((TokenType::KeywordDDL, kw)) if kw == "CREATE" && is_create_table(tokens) => {
+                Statement::CreateTable(Box::new(CreateTable::parse(tokens)))
+            },
+            _ => Statement::Raw(Raw::new(tokens)),
+        }
+    }
+}
+
+/// tokens of a part of a statement that is not recognized
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Raw<'t, 'a> {
+    pub tokens: Vec<&'t Token<'a>>,
+}
+
+impl<'t, 'a> Raw<'t, 'a> {
+
+    // whitespace and comments around the tokens are left out
+    fn new(tokens: &'t [Token<'a>]) -> Self {
+        let start = tokens.iter().position(|t| !is_skipped(t)).unwrap_or(tokens.len());
+        let end = tokens.iter().rposition(|t| !is_skipped(t)).map(|i| i+1).unwrap_or(start);
+        Self { tokens: tokens[start..end].iter().collect() }
+    }
+}
+
+impl fmt::Display for Raw<'_, '_> {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.tokens.iter().try_for_each(|t| f.write_str(t.value()))
+    }
+}
+
+/// possibly qualified name of a table, `schema.table`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ObjectName<'t, 'a> {
+    /// the names and the dots between them
+    pub tokens: Vec<&'t Token<'a>>,
+}
+
+impl<'t, 'a> ObjectName<'t, 'a> {
+
+    // name at the start of the token, an alias or a column list grouped with it is left out
+    fn new(token: &'t Token<'a>) -> Self {
+        if !matches!(token.typ, TokenType::Identifier | TokenType::Function) {
+            return Self { tokens: vec![token] }
+        }
+        let mut tokens = vec![];
+        for child in token.children.tokens.iter() {
+            match child.typ {
+                // `table (a, b)` is grouped as a function named as the table
+                TokenType::Function => {
+                    tokens.extend(child.children.tokens.first());
+                    break
+                },
+                TokenType::Identifier | TokenType::Name | TokenType::StringSymbol => tokens.push(child),
+                TokenType::Punctuation if child.value() == "." => tokens.push(child),
+                _ => break,
+            }
+        }
+        Self { tokens }
+    }
+
+    /// unquoted parts of the name
+    pub fn parts(&self) -> Vec<Cow<'t, str>> {
+        self.tokens.iter().filter(|t| t.typ != TokenType::Punctuation).map(|t| unquote_name(t.value())).collect()
+    }
+
+    pub fn name(&self) -> Option<Cow<'t, str>> {
+        self.parts().pop()
+    }
+
+    /// schema or other qualifier before the name
+    pub fn parent(&self) -> Option<Cow<'t, str>> {
+        let mut parts = self.parts();
+        parts.pop();
+        parts.pop()
+    }
+}
+
+impl fmt::Display for ObjectName<'_, '_> {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.tokens.iter().try_for_each(|t| f.write_str(t.value()))
+    }
+}
+
+/// condition of WHERE, HAVING or ON, the tokens are kept if they are not an expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition<'t, 'a> {
+    Expression(Expression<'t, 'a>),
+    Raw(Raw<'t, 'a>),
+}
+
+impl<'t, 'a> Condition<'t, 'a> {
+
+    // condition of a Where, Having or Join group
+    fn new(clause: &'t Token<'a>) -> Self {
+        match Expression::from_clause(clause) {
+            Some(expr) => Condition::Expression(expr),
+            None if clause.typ == TokenType::Join => Condition::Raw(Raw { tokens: clause.join_condition().unwrap_or(&[]).iter().collect() }),
+            None => Condition::Raw(Raw::new(body(clause))),
+        }
+    }
+}
+
+/// `JOIN relation [ON condition | USING (columns)]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Join<'t, 'a> {
+    pub kind: JoinKind,
+    /// joined table or subquery
+    pub relation: Option<&'t Token<'a>>,
+    pub constraint: JoinConstraint<'t, 'a>,
+    /// the Join group
+    pub token: &'t Token<'a>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JoinConstraint<'t, 'a> {
+    On(Condition<'t, 'a>),
+    Using(Vec<&'t str>),
+    None,
+}
+
+impl<'t, 'a> Join<'t, 'a> {
+
+    fn new(token: &'t Token<'a>) -> Self {
+        let constraint = match token.join_condition() {
+            Some(_) => JoinConstraint::On(Condition::new(token)),
+            None if token.join_using().is_empty() => JoinConstraint::None,
+            None => JoinConstraint::Using(token.join_using()),
+        };
+        Self { kind: token.join_kind().unwrap_or(JoinKind::Inner), relation: token.join_target(), constraint, token }
+    }
+}
+
+/// `SELECT`, the parts after a set operator like `UNION` are raw
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Select<'t, 'a> {
+    /// the With group of common table expressions
+    pub with: Option<&'t Token<'a>>,
+    pub distinct: bool,
+    pub projections: Vec<&'t Token<'a>>,
+    /// tables and subqueries of FROM
+    pub from: Vec<&'t Token<'a>>,
+    pub joins: Vec<Join<'t, 'a>>,
+    /// condition of WHERE
+    pub selection: Option<Condition<'t, 'a>>,
+    pub group_by: Vec<&'t Token<'a>>,
+    pub having: Option<Condition<'t, 'a>>,
+    pub order_by: Vec<&'t Token<'a>>,
+    pub limit: Option<&'t Token<'a>>,
+    pub offset: Option<&'t Token<'a>>,
+    pub raw: Vec<Raw<'t, 'a>>,
+    pub tokens: &'t [Token<'a>],
+}
+
+impl<'t, 'a> Select<'t, 'a> {
+
+    fn parse(tokens: &'t [Token<'a>]) -> Self {
+        let mut select = Select { tokens, ..Default::default() };
+        let mut raw = RawParts::default();
+        let projection = projection(tokens);
+        let mut seen_select = false;
+        let mut set_operation = false;
+        for (idx, token) in tokens.iter().enumerate() {
+            if is_skipped(token) || set_operation {
+                raw.push(token);
+                continue
+            }
+            match token.typ {
+                TokenType::With if !seen_select => select.with = Some(token),
+                TokenType::KeywordDML if !seen_select => seen_select = true,
+                _ if Some(idx) == projection => select.projections = items(std::slice::from_ref(token)),
+                TokenType::Keyword if !seen_select || Some(idx) >= projection => {
+                    set_operation = is_set_operator(token);
+                    raw.push(token);
+                    continue
+                },
+                TokenType::Keyword if is_distinct(token) => {
+                    select.distinct = token.normalized() == "DISTINCT"
+                },
+                TokenType::From => select.from = items(body(token)),
+                TokenType::Join => select.joins.push(Join::new(token)),
+                TokenType::Where => select.selection = Some(Condition::new(token)),
+                TokenType::GroupBy => select.group_by = items(body(token)),
+                TokenType::Having => select.having = Some(Condition::new(token)),
+                TokenType::OrderBy => select.order_by = items(body(token)),
+                TokenType::Limit => select.limit = items(body(token)).first().copied(),
+                TokenType::Offset => select.offset = items(body(token)).first().copied(),
+                _ => {
+                    raw.push(token);
+                    continue
+                },
+            }
+            raw.flush();
+        }
+        select.raw = raw.finish();
+        select
+    }
+}
+
+/// `INSERT INTO table [(columns)] VALUES ... | query`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Insert<'t, 'a> {
+    pub table: Option<ObjectName<'t, 'a>>,
+    pub columns: Vec<&'t Token<'a>>,
+    /// parenthesis of each row of VALUES
+    pub values: Vec<&'t Token<'a>>,
+    /// `INSERT ... SELECT`
+    pub query: Option<Box<Select<'t, 'a>>>,
+    pub raw: Vec<Raw<'t, 'a>>,
+    pub tokens: &'t [Token<'a>],
+}
+
+impl<'t, 'a> Insert<'t, 'a> {
+
+    fn parse(tokens: &'t [Token<'a>]) -> Self {
+        let mut insert = Insert { tokens, ..Default::default() };
+        let mut raw = RawParts::default();
+        let mut seen_into = false;
+        for (idx, token) in tokens.iter().enumerate() {
+            if is_skipped(token) {
+                raw.push(token);
+                continue
+            }
+            match token.typ {
+                TokenType::KeywordDML if !seen_into => {},
+                TokenType::Keyword if !seen_into && token.normalized() == "INTO" => seen_into = true,
+                _ if seen_into && insert.table.is_none() => {
+                    insert.table = Some(ObjectName::new(token));
+                    if let Some(columns) = column_list(token) {
+                        insert.columns = items(inner(columns));
+                    }
+                },
+                TokenType::Parenthesis if insert.table.is_some() && insert.columns.is_empty() && insert.values.is_empty() => {
+                    insert.columns = items(inner(token))
+                },
+                TokenType::Values => {
+                    insert.values = token.children.tokens.iter().filter(|t| t.typ == TokenType::Parenthesis).collect()
+                },
+                TokenType::KeywordDML | TokenType::With | TokenType::Subquery if insert.table.is_some() => {
+                    raw.flush();
+                    insert.query = Some(Box::new(Select::parse(&tokens[idx..])));
+                    break
+                },
+                _ => {
+                    raw.push(token);
+                    continue
+                },
+            }
+            raw.flush();
+        }
+        insert.raw = raw.finish();
+        insert
+    }
+}
+
+/// `UPDATE table SET assignments [FROM ...] [WHERE ...]`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Update<'t, 'a> {
+    pub table: Option<&'t Token<'a>>,
+    /// comparison tokens like `a = 1`
+    pub assignments: Vec<&'t Token<'a>>,
+    pub from: Vec<&'t Token<'a>>,
+    pub selection: Option<Condition<'t, 'a>>,
+    pub raw: Vec<Raw<'t, 'a>>,
+    pub tokens: &'t [Token<'a>],
+}
+
+impl<'t, 'a> Update<'t, 'a> {
+
+    fn parse(tokens: &'t [Token<'a>]) -> Self {
+        let mut update = Update { tokens, ..Default::default() };
+        let mut raw = RawParts::default();
+        let mut seen_update = false;
+        let mut seen_set = false;
+        for token in tokens {
+            if is_skipped(token) {
+                raw.push(token);
+                continue
+            }
+            match token.typ {
+                TokenType::KeywordDML if !seen_update => seen_update = true,
+                TokenType::Keyword if !seen_set && token.normalized() == "SET" => seen_set = true,
+                _ if seen_update && !seen_set && update.table.is_none() => update.table = Some(token),
+                _ if seen_set && update.assignments.is_empty() && !token.typ.is_clause() && token.typ != TokenType::Where => {
+                    update.assignments = items(std::slice::from_ref(token))
+                },
+                TokenType::From => update.from = items(body(token)),
+                TokenType::Where => update.selection = Some(Condition::new(token)),
+                _ => {
+                    raw.push(token);
+                    continue
+                },
+            }
+            raw.flush();
+        }
+        update.raw = raw.finish();
+        update
+    }
+}
+
+/// `DELETE FROM table [USING ...] [WHERE ...]`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Delete<'t, 'a> {
+    pub table: Option<&'t Token<'a>>,
+    pub using: Vec<&'t Token<'a>>,
+    pub selection: Option<Condition<'t, 'a>>,
+    pub raw: Vec<Raw<'t, 'a>>,
+    pub tokens: &'t [Token<'a>],
+}
+
+impl<'t, 'a> Delete<'t, 'a> {
+
+    fn parse(tokens: &'t [Token<'a>]) -> Self {
+        let mut delete = Delete { tokens, ..Default::default() };
+        let mut raw = RawParts::default();
+        for token in tokens {
+            if is_skipped(token) {
+                raw.push(token);
+                continue
+            }
+            match token.typ {
+                TokenType::KeywordDML if token.normalized() == "DELETE" => {},
+                TokenType::From if delete.table.is_none() => {
+                    let body = body(token);
+                    let using = body.iter().position(|t| t.typ == TokenType::Keyword && t.normalized() == "USING").unwrap_or(body.len());
+                    delete.table = items(&body[..using]).first().copied();
+                    delete.using = items(body.get(using+1..).unwrap_or(&[]));
+                },
+                TokenType::Where => delete.selection = Some(Condition::new(token)),
+                _ => {
+                    raw.push(token);
+                    continue
+                },
+            }
+            raw.flush();
+        }
+        delete.raw = raw.finish();
+        delete
+    }
+}
+
+/// `CREATE TABLE [IF NOT EXISTS] name (columns, constraints)`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CreateTable<'t, 'a> {
+    pub if_not_exists: bool,
+    pub name: Option<&'t Token<'a>>,
+    pub columns: Vec<ColumnDef<'t, 'a>>,
+    /// table constraints like `PRIMARY KEY (a, b)`
+    pub constraints: Vec<Raw<'t, 'a>>,
+    pub raw: Vec<Raw<'t, 'a>>,
+    pub tokens: &'t [Token<'a>],
+}
+
+/// `name data_type [options]` of CREATE TABLE
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnDef<'t, 'a> {
+    pub name: &'t Token<'a>,
+    pub data_type: Vec<&'t Token<'a>>,
+    /// constraints and default value of the column
+    pub options: Vec<&'t Token<'a>>,
+}
+
+impl<'t, 'a> CreateTable<'t, 'a> {
+
+    fn parse(tokens: &'t [Token<'a>]) -> Self {
+        let mut create = CreateTable { tokens, ..Default::default() };
+        let mut raw = RawParts::default();
+        let mut seen_table = false;
+        for token in tokens {
+            if is_skipped(token) {
+                raw.push(token);
+                continue
+            }
+            match token.typ {
+                TokenType::KeywordDDL if !seen_table => {},
+                TokenType::Keyword if !seen_table => seen_table = token.normalized() == "TABLE",
+                TokenType::Keyword if create.name.is_none() && matches!(token.normalized(), "IF" | "NOT" | "EXISTS") => {
+                    create.if_not_exists = true
+                },
+                _ if seen_table && create.name.is_none() => create.name = Some(token),
+                TokenType::Parenthesis if create.columns.is_empty() && create.constraints.is_empty() => {
+                    for definition in split(inner(token)) {
+                        match column_def(&definition) {
+                            Some(column) => create.columns.push(column),
+                            None => create.constraints.push(Raw { tokens: definition }),
+                        }
+                    }
+                },
+                _ => {
+                    raw.push(token);
+                    continue
+                },
+            }
+            raw.flush();
+        }
+        create.raw = raw.finish();
+        create
+    }
+}
+
+// consecutive tokens that are not recognized, whitespace between them is kept
+#[derive(Default)]
+struct RawParts<'t, 'a> {
+    parts: Vec<Raw<'t, 'a>>,
+    pending: Vec<&'t Token<'a>>,
+}
+
+impl<'t, 'a> RawParts<'t, 'a> {
+
+    fn push(&mut self, token: &'t Token<'a>) {
+        if !is_skipped(token) || !self.pending.is_empty() {
+            self.pending.push(token);
+        }
+    }
+
+    fn flush(&mut self) {
+        while self.pending.last().map(|t| is_skipped(t)).unwrap_or(false) {
+            self.pending.pop();
+        }
+        if !self.pending.is_empty() {
+            self.parts.push(Raw { tokens: std::mem::take(&mut self.pending) });
+        }
+    }
+
+    fn finish(mut self) -> Vec<Raw<'t, 'a>> {
+        self.flush();
+        self.parts
+    }
+}
+
+fn is_create_table(tokens: &[Token]) -> bool {
+    tokens.iter().filter(|t| !is_skipped(t)).take(4).any(|t| t.typ == TokenType::Keyword && t.normalized() == "TABLE")
+}
+
+fn is_distinct(token: &Token) -> bool {
+    token.typ == TokenType::Keyword && matches!(token.normalized(), "DISTINCT" | "ALL")
+}
+
+fn is_set_operator(token: &Token) -> bool {
+    token.typ == TokenType::Keyword && ["UNION", "INTERSECT", "EXCEPT", "MINUS"].iter().any(|s| token.normalized().starts_with(s))
+}
+
+// the last token between SELECT [DISTINCT] and the first clause
+fn projection(tokens: &[Token]) -> Option<usize> {
+    let start = tokens.iter().position(|t| t.typ == TokenType::KeywordDML)?;
+    let end = tokens.iter().skip(start+1)
+        .position(|t| t.typ.is_clause() || t.typ == TokenType::Where || is_set_operator(t) || t.normalized() == "INTO" || t.value() == ";")
+        .map(|i| i+start+1)
+        .unwrap_or(tokens.len());
+    tokens[start+1..end].iter().rposition(|t| !is_skipped(t) && !is_distinct(t))
+        .map(|i| i+start+1)
+}
+
+// tokens of a clause after its keyword
+fn body<'t, 'a>(clause: &'t Token<'a>) -> &'t [Token<'a>] {
+    let tokens = &clause.children.tokens;
+    let start = tokens.iter().position(|t| t.is_keyword()).map(|i| i+1).unwrap_or(0);
+    &tokens[start..]
+}
+
+// tokens between the parenthesis
+fn inner<'t, 'a>(parenthesis: &'t Token<'a>) -> &'t [Token<'a>] {
+    let tokens = &parenthesis.children.tokens;
+    tokens.get(1..tokens.len().saturating_sub(1)).unwrap_or(&[])
+}
+
+// items of a comma separated list
+fn items<'t, 'a>(tokens: &'t [Token<'a>]) -> Vec<&'t Token<'a>> {
+    split(tokens).into_iter().flatten().filter(|t| !is_skipped(t)).collect()
+}
+
+// tokens of a comma separated list by item, without the whitespace around the items
+fn split<'t, 'a>(tokens: &'t [Token<'a>]) -> Vec<Vec<&'t Token<'a>>> {
+    let tokens = tokens.iter()
+        .flat_map(|t| if t.typ == TokenType::IdentifierList { t.children.tokens.iter().collect() } else { vec![t] })
+        .collect::<Vec<_>>();
+    tokens.split(|t| t.typ == TokenType::Punctuation && t.value() == ",")
+        .filter_map(|item| {
+            let start = item.iter().position(|t| !is_skipped(t))?;
+            let end = item.iter().rposition(|t| !is_skipped(t))?;
+            Some(item[start..end+1].to_vec())
+        })
+        .collect()
+}
+
+// column list of `table (a, b)`, grouped as a function
+fn column_list<'t, 'a>(token: &'t Token<'a>) -> Option<&'t Token<'a>> {
+    match token.typ {
+        TokenType::Function => token.children.tokens.iter().find(|t| t.typ == TokenType::Parenthesis),
+        TokenType::Identifier => token.children.tokens.iter().rev().find(|t| !is_skipped(t)).filter(|t| t.typ == TokenType::Function).and_then(column_list),
+        _ => None,
+    }
+}
+
+fn column_def<'t, 'a>(definition: &[&'t Token<'a>]) -> Option<ColumnDef<'t, 'a>> {
+    let definition = definition.iter().copied().filter(|t| !is_skipped(t)).collect::<Vec<_>>();
+    let (name, rest) = definition.split_first()?;
+    if !matches!(name.typ, TokenType::Identifier | TokenType::Name | TokenType::StringSymbol) {
+        return None
+    }
+    let is_option = |t: &Token| t.is_keyword() && matches!(t.normalized(),
+        "NOT NULL" | "NULL" | "NOT" | "PRIMARY" | "UNIQUE" | "DEFAULT" | "REFERENCES" | "CHECK" | "CONSTRAINT" | "COLLATE" |
+        "GENERATED" | "AUTO_INCREMENT" | "AUTOINCREMENT" | "IDENTITY" | "COMMENT");
+    let split = rest.iter().position(|t| is_option(t)).unwrap_or(rest.len());
+    Some(ColumnDef { name, data_type: rest[..split].to_vec(), options: rest[split..].to_vec() })
+}
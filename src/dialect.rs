@@ -0,0 +1,415 @@
+//! SQL dialects
+//!
+//! A dialect controls how the lexer quotes identifiers, escapes strings,
+//! classifies keywords and recognizes placeholders, and how the splitter
+//! terminates statements.
+//!
+//! ```
+//! use sqlparse::{Parser, PostgreSqlDialect, TokenType};
+//!
+//! let p = Parser::with_dialect(PostgreSqlDialect);
+//! let tokens = p.parse_no_grouping("select $1");
+//! assert_eq!(tokens[2].typ, TokenType::NamePlaceholder);
+//! ```
+
+use crate::tokens::TokenType;
+use crate::keywords::{KEYWORDS_POSTGRES, KEYWORDS_MYSQL, KEYWORDS_ORACLE};
+
+/// placeholder styles recognized by the lexer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placeholder {
+    /// `?` and `?name`
+    QuestionMark,
+    /// `%s` and `%(name)s`
+    Format,
+    /// `:name` and `:1`
+    Colon,
+    /// `$1` and `$name`
+    Dollar,
+    /// `@name`
+    At,
+}
+
+pub trait Dialect: Send+Sync {
+
+    fn name(&self) -> &'static str;
+
+    /// Characters that delimit quoted identifiers, `[` is closed by `]`.
+    /// `"` identifiers are lexed as `TokenType::StringSymbol`, if `"` is missing, double quoted text is a string.
+    fn identifier_quotes(&self) -> &'static [char] {
+        &['"']
+    }
+
+    /// Characters allowed in unquoted identifiers besides the Unicode XID characters and `_`.
+    fn identifier_chars(&self) -> &'static [char] {
+        &['$']
+    }
+
+    /// If true backslash escapes quotes inside string literals.
+    fn backslash_escapes(&self) -> bool {
+        false
+    }
+
+    fn placeholders(&self) -> &'static [Placeholder] {
+        &[Placeholder::QuestionMark]
+    }
+
+    /// If true `$$...$$` and `$tag$...$tag$` are string literals.
+    fn dollar_quoted_strings(&self) -> bool {
+        false
+    }
+
+    /// If true `E'...'` strings with backslash escapes are string literals.
+    fn escape_strings(&self) -> bool {
+        false
+    }
+
+    /// If true `r'...'`, `b'...'` and triple quoted `'''...'''` are string literals.
+    fn raw_strings(&self) -> bool {
+        false
+    }
+
+    /// If true block comments nest, `/* a /* b */ c */` is a single comment.
+    fn nested_comments(&self) -> bool {
+        false
+    }
+
+    /// If true `@var` and `@@var` are variable names.
+    fn at_variables(&self) -> bool {
+        false
+    }
+
+    /// Token type of an uppercased word, `None` falls back to the common keyword table.
+    fn keyword(&self, _word: &str) -> Option<TokenType> {
+        None
+    }
+
+    /// Punctuations that end a statement.
+    fn statement_terminators(&self) -> &'static [&'static str] {
+        &[";"]
+    }
+
+    /// Keyword on its own line that ends a batch, like `GO` of SQL Server.
+    fn batch_separator(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Punctuation on its own line that ends a PL/SQL block, like `/` of SQL*Plus.
+    /// Inside a block the statement terminators are ignored.
+    fn block_terminator(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// If true the `DELIMITER` client command of MySQL changes the statement terminator.
+    fn delimiter_command(&self) -> bool {
+        false
+    }
+}
+
+// words reserved by other databases are plain names
+fn vendor_name(word: &str, vendors: &[&[&str]]) -> Option<TokenType> {
+    if vendors.iter().any(|keywords| keywords.contains(&word)) { Some(TokenType::Name) } else { None }
+}
+
+/// Accepts the keywords and syntax of all supported databases, the default dialect.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GenericDialect;
+
+impl Dialect for GenericDialect {
+
+    fn name(&self) -> &'static str {
+        "generic"
+    }
+
+    fn identifier_quotes(&self) -> &'static [char] {
+        &['`', '´', '"']
+    }
+
+    fn identifier_chars(&self) -> &'static [char] {
+        &['$', '#']
+    }
+
+    fn backslash_escapes(&self) -> bool {
+        true
+    }
+
+    fn placeholders(&self) -> &'static [Placeholder] {
+        &[Placeholder::QuestionMark, Placeholder::Format, Placeholder::Colon, Placeholder::Dollar]
+    }
+
+    fn dollar_quoted_strings(&self) -> bool {
+        true
+    }
+
+    fn escape_strings(&self) -> bool {
+        true
+    }
+
+    fn nested_comments(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AnsiDialect;
+
+impl Dialect for AnsiDialect {
+
+    fn name(&self) -> &'static str {
+        "ansi"
+    }
+
+    fn identifier_chars(&self) -> &'static [char] {
+        &[]
+    }
+
+    fn placeholders(&self) -> &'static [Placeholder] {
+        &[Placeholder::QuestionMark, Placeholder::Colon]
+    }
+
+    fn keyword(&self, word: &str) -> Option<TokenType> {
+        vendor_name(word, &[&KEYWORDS_POSTGRES, &KEYWORDS_MYSQL, &KEYWORDS_ORACLE])
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PostgreSqlDialect;
+
+impl Dialect for PostgreSqlDialect {
+
+    fn name(&self) -> &'static str {
+        "postgresql"
+    }
+
+    fn placeholders(&self) -> &'static [Placeholder] {
+        &[Placeholder::Dollar, Placeholder::Format]
+    }
+
+    fn dollar_quoted_strings(&self) -> bool {
+        true
+    }
+
+    fn escape_strings(&self) -> bool {
+        true
+    }
+
+    fn nested_comments(&self) -> bool {
+        true
+    }
+
+    fn keyword(&self, word: &str) -> Option<TokenType> {
+        vendor_name(word, &[&KEYWORDS_MYSQL, &KEYWORDS_ORACLE])
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MySqlDialect;
+
+impl Dialect for MySqlDialect {
+
+    fn name(&self) -> &'static str {
+        "mysql"
+    }
+
+    fn identifier_quotes(&self) -> &'static [char] {
+        &['`']
+    }
+
+    fn backslash_escapes(&self) -> bool {
+        true
+    }
+
+    fn placeholders(&self) -> &'static [Placeholder] {
+        &[Placeholder::QuestionMark, Placeholder::Format]
+    }
+
+    fn at_variables(&self) -> bool {
+        true
+    }
+
+    fn delimiter_command(&self) -> bool {
+        true
+    }
+
+    fn keyword(&self, word: &str) -> Option<TokenType> {
+        match word {
+            "REGEXP" | "ZEROFILL" => Some(TokenType::Keyword),
+            "DATETIME" | "ENUM" | "MEDIUMINT" | "TINYTEXT" | "MEDIUMTEXT" | "LONGTEXT" | "JSON" => Some(TokenType::NameBuiltin),
+            _ => vendor_name(word, &[&KEYWORDS_POSTGRES, &KEYWORDS_ORACLE]),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SQLiteDialect;
+
+impl Dialect for SQLiteDialect {
+
+    fn name(&self) -> &'static str {
+        "sqlite"
+    }
+
+    fn identifier_quotes(&self) -> &'static [char] {
+        &['`', '"', '[']
+    }
+
+    fn placeholders(&self) -> &'static [Placeholder] {
+        &[Placeholder::QuestionMark, Placeholder::Colon, Placeholder::Dollar, Placeholder::At]
+    }
+
+    fn keyword(&self, word: &str) -> Option<TokenType> {
+        match word {
+            "AUTOINCREMENT" | "PRAGMA" | "GLOB" => Some(TokenType::Keyword),
+            _ => vendor_name(word, &[&KEYWORDS_POSTGRES, &KEYWORDS_MYSQL, &KEYWORDS_ORACLE]),
+        }
+    }
+}
+
+/// Microsoft SQL Server T-SQL
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MsSqlDialect;
+
+impl Dialect for MsSqlDialect {
+
+    fn name(&self) -> &'static str {
+        "mssql"
+    }
+
+    fn identifier_quotes(&self) -> &'static [char] {
+        &['"', '[']
+    }
+
+    fn identifier_chars(&self) -> &'static [char] {
+        &['$', '#', '@']
+    }
+
+    fn at_variables(&self) -> bool {
+        true
+    }
+
+    fn nested_comments(&self) -> bool {
+        true
+    }
+
+    fn batch_separator(&self) -> Option<&'static str> {
+        Some("GO")
+    }
+
+    fn keyword(&self, word: &str) -> Option<TokenType> {
+        match word {
+            "TOP" | "NOLOCK" | "IDENTITY" => Some(TokenType::Keyword),
+            "DATETIME" | "DATETIME2" | "SMALLDATETIME" | "UNIQUEIDENTIFIER" | "MONEY" | "NTEXT" => Some(TokenType::NameBuiltin),
+            _ => vendor_name(word, &[&KEYWORDS_POSTGRES, &KEYWORDS_MYSQL, &KEYWORDS_ORACLE]),
+        }
+    }
+}
+
+/// Oracle SQL and SQL*Plus scripts
+#[derive(Debug, Default, Clone, Copy)]
+pub struct OracleDialect;
+
+impl Dialect for OracleDialect {
+
+    fn name(&self) -> &'static str {
+        "oracle"
+    }
+
+    fn identifier_chars(&self) -> &'static [char] {
+        &['$', '#']
+    }
+
+    fn placeholders(&self) -> &'static [Placeholder] {
+        &[Placeholder::Colon]
+    }
+
+    fn block_terminator(&self) -> Option<&'static str> {
+        Some("/")
+    }
+
+    fn keyword(&self, word: &str) -> Option<TokenType> {
+        vendor_name(word, &[&KEYWORDS_POSTGRES, &KEYWORDS_MYSQL])
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BigQueryDialect;
+
+impl Dialect for BigQueryDialect {
+
+    fn name(&self) -> &'static str {
+        "bigquery"
+    }
+
+    fn identifier_quotes(&self) -> &'static [char] {
+        &['`']
+    }
+
+    fn identifier_chars(&self) -> &'static [char] {
+        &[]
+    }
+
+    fn backslash_escapes(&self) -> bool {
+        true
+    }
+
+    fn placeholders(&self) -> &'static [Placeholder] {
+        &[Placeholder::QuestionMark, Placeholder::At]
+    }
+
+    fn raw_strings(&self) -> bool {
+        true
+    }
+
+    fn keyword(&self, word: &str) -> Option<TokenType> {
+        match word {
+            "QUALIFY" | "STRUCT" => Some(TokenType::Keyword),
+            "INT64" | "FLOAT64" | "BIGNUMERIC" | "BOOL" | "BYTES" | "STRING" | "DATETIME" | "GEOGRAPHY" | "JSON" => Some(TokenType::NameBuiltin),
+            _ => vendor_name(word, &[&KEYWORDS_POSTGRES, &KEYWORDS_MYSQL, &KEYWORDS_ORACLE]),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SnowflakeDialect;
+
+impl Dialect for SnowflakeDialect {
+
+    fn name(&self) -> &'static str {
+        "snowflake"
+    }
+
+    fn backslash_escapes(&self) -> bool {
+        true
+    }
+
+    fn placeholders(&self) -> &'static [Placeholder] {
+        &[Placeholder::QuestionMark, Placeholder::Colon]
+    }
+
+    fn dollar_quoted_strings(&self) -> bool {
+        true
+    }
+
+    fn keyword(&self, word: &str) -> Option<TokenType> {
+        match word {
+            "QUALIFY" => Some(TokenType::Keyword),
+            "VARIANT" | "TIMESTAMP_NTZ" | "TIMESTAMP_LTZ" | "TIMESTAMP_TZ" | "NUMBER" | "STRING" => Some(TokenType::NameBuiltin),
+            _ => vendor_name(word, &[&KEYWORDS_POSTGRES, &KEYWORDS_MYSQL, &KEYWORDS_ORACLE]),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vendor_name() {
+        assert_eq!(AnsiDialect.keyword("JSONB"), Some(TokenType::Name));
+        assert_eq!(AnsiDialect.keyword("SELECT"), None);
+        assert_eq!(PostgreSqlDialect.keyword("JSONB"), None);
+        assert_eq!(MsSqlDialect.keyword("MONEY"), Some(TokenType::NameBuiltin));
+        assert_eq!(OracleDialect.keyword("VARCHAR2"), None);
+    }
+}
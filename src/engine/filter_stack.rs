@@ -1,11 +1,15 @@
-use crate::lexer::{Token, TokenList, tokenize_internal};
-use crate::keywords::{RegexToken, sql_regex, init_trie};
+use std::io::BufRead;
+use crate::lexer::{Token, TokenList, LexError, Resume, tokenize_internal, try_tokenize_internal, tokenize_resume, unclosed_offset};
+use crate::keywords::{RegexToken, sql_regex_dialect, init_trie};
+use crate::dialect::{Dialect, GenericDialect};
 use crate::filters::{Filter, StmtFilter, TokenListFilter};
 use crate::trie::TokenTypeTrie;
-use super::splitter::StatementSplitter;
+use super::splitter::{StatementSplitter, StatementSlice};
+use super::stream::StatementStream;
 
 // 'a
 pub struct FilterStack {
+    dialect: Box<dyn Dialect>,
     regs: Vec<RegexToken>,
     trie: TokenTypeTrie,
     spliter: StatementSplitter,
@@ -19,10 +23,15 @@ pub struct FilterStack {
 impl FilterStack {
 
     pub fn new() -> Self {
+        Self::with_dialect(Box::new(GenericDialect))
+    }
+
+    pub fn with_dialect(dialect: Box<dyn Dialect>) -> Self {
         Self { 
-            regs: sql_regex(),
+            regs: sql_regex_dialect(dialect.as_ref()),
             trie: init_trie(),
-            spliter: StatementSplitter::default(),
+            spliter: StatementSplitter::new(dialect.as_ref()),
+            dialect,
             preprocess: vec![],
             stmtprocess: vec![],
             postprocess: vec![],
@@ -31,23 +40,58 @@ impl FilterStack {
     }
 
     // TODO: support more than one sql
-    pub fn run(&self, sql: &str, grouping: bool) -> Vec<Token> {
-        let mut tokens = tokenize_internal(sql, &self.regs, &self.trie);
+    pub fn run<'a>(&self, sql: &'a str, grouping: bool) -> Vec<Token<'a>> {
+        let mut tokens = tokenize_internal(sql, &self.regs, &self.trie, self.dialect.as_ref());
         if grouping {
             tokens = super::grouping::group(tokens);
         }
         tokens
     }
 
-    pub fn run_multi(&self, sql: &str, grouping: bool) -> Vec<Vec<Token>> {
-        let tokens = tokenize_internal(sql, &self.regs, &self.trie);
+    pub fn run_multi<'a>(&self, sql: &'a str, grouping: bool) -> Vec<Vec<Token<'a>>> {
+        let tokens = tokenize_internal(sql, &self.regs, &self.trie, self.dialect.as_ref());
         let stmts = self.spliter.process(tokens);
         stmts.into_iter().map(|tokens| if grouping { super::grouping::group(tokens) } else { tokens }).collect()
     }
 
+    pub fn try_run<'a>(&self, sql: &'a str, grouping: bool) -> Result<Vec<Token<'a>>, LexError> {
+        let mut tokens = try_tokenize_internal(sql, &self.regs, &self.trie, self.dialect.as_ref())?;
+        if grouping {
+            tokens = super::grouping::group(tokens);
+        }
+        Ok(tokens)
+    }
+
+    pub fn try_run_multi<'a>(&self, sql: &'a str, grouping: bool) -> Result<Vec<Vec<Token<'a>>>, LexError> {
+        let tokens = try_tokenize_internal(sql, &self.regs, &self.trie, self.dialect.as_ref())?;
+        let stmts = self.spliter.process(tokens);
+        Ok(stmts.into_iter().map(|tokens| if grouping { super::grouping::group(tokens) } else { tokens }).collect())
+    }
+
+    /// statements of a script without grouping their tokens
+    pub fn split<'a>(&self, sql: &'a str) -> Vec<StatementSlice<'a>> {
+        let tokens = tokenize_internal(sql, &self.regs, &self.trie, self.dialect.as_ref());
+        self.spliter.split_statements(tokens, None, true).iter()
+            .filter_map(|(tokens, terminator)| StatementSlice::new(sql, tokens, *terminator))
+            .collect()
+    }
+
+    /// statements of a script read line by line
+    pub fn run_stream<R: BufRead>(&self, reader: R, grouping: bool) -> StatementStream<'_, R> {
+        StatementStream::new(self, reader, grouping)
+    }
+
+    // statements of the rest of a script from a statement boundary,
+    // with the offset of the first quote or comment left open
+    pub(crate) fn split_resume<'a>(&self, sql: &'a str, resume: &Resume) -> (Vec<Vec<Token<'a>>>, Option<usize>) {
+        let tokens = tokenize_resume(sql, &self.regs, &self.trie, self.dialect.as_ref(), resume);
+        let unclosed = unclosed_offset(&tokens, self.dialect.as_ref());
+        (self.spliter.process_resume(tokens, resume.delimiter(), resume.line_start()), unclosed)
+    }
+
     // format sql
-    pub fn format(&mut self, sql: &str, grouping: bool) -> Vec<Vec<Token>> {
-        let mut tokens = tokenize_internal(sql, &self.regs, &self.trie);
+    pub fn format<'a>(&mut self, sql: &'a str, grouping: bool) -> Vec<Vec<Token<'a>>> {
+        let mut tokens = tokenize_internal(sql, &self.regs, &self.trie, self.dialect.as_ref());
         for token in tokens.iter_mut() {
             self.preprocess.iter().for_each(|filter| filter.process(token));
         }
@@ -1,10 +1,11 @@
 use std::convert::From;
 use std::fmt;
-use crate::lexer::{Token, TokenList, tokenize, remove_quotes};
+use std::borrow::Cow;
+use crate::lexer::{Token, TokenList, Span, tokenize, unquote_name};
 use crate::tokens::TokenType;
 
 const T_NUMERICAL: [TokenType; 3] = [TokenType::Number, TokenType::NumberInteger, TokenType::NumberFloat];
-const T_STRING: [TokenType; 3] = [TokenType::String, TokenType::StringSingle, TokenType::StringSymbol];
+const T_STRING: [TokenType; 4] = [TokenType::String, TokenType::StringSingle, TokenType::StringSymbol, TokenType::StringDollar];
 const T_NAME: [TokenType; 2] = [TokenType::Name, TokenType::NamePlaceholder];
 
 macro_rules! sub_group {
@@ -20,26 +21,29 @@ macro_rules! sub_group {
 pub fn group(tokens: Vec<Token>) -> Vec<Token> {
     let mut token_list = TokenList::new(tokens);
     token_list.group();
+    token_list.shrink_to_fit();
     token_list.tokens
 }
 
 /// parse sql into grouped TokenList.
 /// only for test
-pub fn group_tokenlist(sql: &str) -> TokenList {
+pub fn group_tokenlist(sql: &str) -> TokenList<'_> {
     let mut token_list = TokenList::from(sql);
     token_list.group();
+    token_list.shrink_to_fit();
     token_list
 }
 
-impl From<&str> for TokenList {
+impl<'a> From<&'a str> for TokenList<'a> {
+
     
-    fn from(sql: &str) -> Self {
+    fn from(sql: &'a str) -> Self {
         let tokens = tokenize(sql);
         TokenList::new(tokens)
     }
 }
 
-impl std::fmt::Display for TokenList {
+impl std::fmt::Display for TokenList<'_> {
 
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         for token in &self.tokens {
@@ -50,10 +54,10 @@ impl std::fmt::Display for TokenList {
 }
 
 // TODO: GroupToken
-impl TokenList {
+impl<'a> TokenList<'a> {
 
     /// Create a new TokenList
-    pub fn new(tokens: Vec<Token>) -> Self {
+    pub fn new(tokens: Vec<Token<'a>>) -> Self {
         // let group_tokens = tokens.into_iter().map(|t| t.into()).collect();
         Self { tokens: tokens }
     }
@@ -63,7 +67,7 @@ impl TokenList {
     pub fn groupable_tokens(&self, token: &Token) -> (usize, usize) {
         match token.typ {
             TokenType::Parenthesis | TokenType::SquareBrackets => (1, self.len()-1),
-            TokenType::Punctuation if token.value == "(" || token.value == "[" => (1, self.len()-1),
+            TokenType::Punctuation if token.value() == "(" || token.value() == "[" => (1, self.len()-1),
             _ => (0, self.len())
         }
     }
@@ -74,7 +78,7 @@ impl TokenList {
 
     // join first n value
     pub fn take_value(&self, idx: usize) -> String {
-        self.tokens.iter().take(idx).map(|t| t.value.as_str()).collect::<Vec<&str>>().join("")
+        self.tokens.iter().take(idx).map(|t| t.value()).collect::<Vec<&str>>().join("")
     }
 
     fn token_matching(&self, types: &[TokenType], pattern: Option<&(TokenType, Vec<&str>)>, start: usize, end: usize) -> Option<usize> {
@@ -83,7 +87,7 @@ impl TokenList {
                 .position(|token| types.iter().find(|t| **t == token.typ).is_some())
         } else if let Some(p) = pattern {
             self.tokens[start..end].iter()
-                .position(|token| p.0 == token.typ && p.1.iter().find(|v| **v == token.normalized).is_some())
+                .position(|token| p.0 == token.typ && p.1.iter().find(|v| **v == token.normalized()).is_some())
         } else {
             None
         };
@@ -128,11 +132,11 @@ impl TokenList {
         else {  self.token_matching_fn(|_| true, 0, idx, true) }
     }
 
-    pub fn token_idx(&self, idx: Option<usize>) -> Option<&Token> {
+    pub fn token_idx(&self, idx: Option<usize>) -> Option<&Token<'a>> {
         idx.map(|i| self.tokens.get(i)).flatten()
     }
 
-    pub fn extend(&mut self, tokens: Vec<Token>) {
+    pub fn extend(&mut self, tokens: Vec<Token<'a>>) {
         self.tokens.extend(tokens)
     }
 
@@ -143,7 +147,8 @@ impl TokenList {
             let sub_tokens = self.tokens[start_idx+1..end].to_vec();
             let start = &mut self.tokens[start_idx];
             start.children.extend(sub_tokens);
-            start.value = Token::new_value(&start.children.tokens);
+            start.update_value();
+            start.span = Span::union(&start.children.tokens);
             self.tokens.splice(start_idx+1..end, []).for_each(drop);
             return
         }
@@ -152,13 +157,13 @@ impl TokenList {
         self.tokens.splice(start..end, group_token).for_each(drop);
     }
 
-    pub fn insert_before(&mut self, index: usize, token: Token){
+    pub fn insert_before(&mut self, index: usize, token: Token<'a>){
         self.tokens.insert(index, token)
     }
 
     // insert newline and remove before space
     // return true if any space removed
-    pub fn insert_newline_before(&mut self, index: usize, token: Token) -> bool {
+    pub fn insert_newline_before(&mut self, index: usize, token: Token<'a>) -> bool {
         if index > 0 && self.tokens.get(index-1).map(|t| t.is_whitespace()).unwrap_or(false) {
             self.tokens[index-1] = token;
             true
@@ -168,7 +173,7 @@ impl TokenList {
         }
     }
 
-    pub fn insert_after(&mut self, index: usize, token: Token, skip_ws: bool) {
+    pub fn insert_after(&mut self, index: usize, token: Token<'a>, skip_ws: bool) {
         let nidx = self.token_next(index+1, skip_ws);
         if let Some(idx) = nidx {
             self.tokens.insert(idx, token)
@@ -178,7 +183,7 @@ impl TokenList {
     }
 
     // return true if any space removed
-    pub fn insert_newline_after(&mut self, index: usize, token: Token, skip_ws: bool) -> bool {
+    pub fn insert_newline_after(&mut self, index: usize, token: Token<'a>, skip_ws: bool) -> bool {
         let nidx = self.token_next(index+1, skip_ws);
         let mut whitespace_removed = false;
         if let Some(idx) = nidx {
@@ -199,19 +204,19 @@ impl TokenList {
         let mut mode = 1;
         let mut ret: Vec<(Vec<usize>, Vec<usize>)> = vec![];
         for (idx, token) in self.tokens.iter().enumerate() {
-            if token.typ == TokenType::Keyword && token.normalized == "CASE" {
+            if token.typ == TokenType::Keyword && token.normalized() == "CASE" {
                 continue
             } else if skip_ws && token.is_whitespace() {
                 continue
-            } else if token.typ == TokenType::Keyword && token.normalized == "WHEN" {
+            } else if token.typ == TokenType::Keyword && token.normalized() == "WHEN" {
                 ret.push((vec![], vec![]));
                 mode = 1;
-            } else if token.typ == TokenType::Keyword && token.normalized == "THEN" {
+            } else if token.typ == TokenType::Keyword && token.normalized() == "THEN" {
                 mode = 2;
-            } else if token.typ == TokenType::Keyword && token.normalized == "ELSE" {
+            } else if token.typ == TokenType::Keyword && token.normalized() == "ELSE" {
                 ret.push((vec![], vec![]));
                 mode = 2;
-            } else if token.typ == TokenType::Keyword && token.normalized == "END" {
+            } else if token.typ == TokenType::Keyword && token.normalized() == "END" {
                 mode = 0;
             }
 
@@ -232,7 +237,7 @@ impl TokenList {
 
     //  Whitespaces and punctuations are not included
     pub fn get_identifiers(&self) -> Vec<usize> {
-        self.tokens.iter().enumerate().filter(|(_, t)| !(t.is_whitespace() || t.value == ",")).map(|(i, _)| i).collect::<Vec<_>>()
+        self.tokens.iter().enumerate().filter(|(_, t)| !(t.is_whitespace() || t.value() == ",")).map(|(i, _)| i).collect::<Vec<_>>()
     }
 
     fn group_brackets(&mut self) {
@@ -263,7 +268,7 @@ impl TokenList {
     fn group_typecasts(&mut self) {
         
         fn matcher(token: &Token) -> bool {
-            token.typ == TokenType::Punctuation && token.value == "::"
+            token.typ == TokenType::Punctuation && token.value() == "::"
         }
 
         fn valid(token: Option<&Token>) -> bool {
@@ -299,7 +304,7 @@ impl TokenList {
 
         fn matcher(token: &Token) -> bool {
             token.typ == TokenType::NameBuiltin ||
-            (token.typ == TokenType::Keyword && token.normalized == "TIMESTAMP")
+            (token.typ == TokenType::Keyword && token.normalized() == "TIMESTAMP")
         }
 
         fn match_to_extend(token: &Token) -> bool {
@@ -315,7 +320,7 @@ impl TokenList {
         }
 
         fn valid_final(token: Option<&Token>) -> bool {
-            token.map(|t| t.typ == TokenType::Keyword && match t.normalized.as_str() {
+            token.map(|t| t.typ == TokenType::Keyword && match t.normalized() {
                 "DAY" | "HOUR" | "MINUTE" | "MONTH" | "SECOND" | "YEAR" => true,
                 _ => false}).unwrap_or(false)
         }
@@ -349,7 +354,7 @@ impl TokenList {
     fn group_identifier_list(&mut self) {
 
         fn matcher(token: &Token) -> bool {
-            token.typ == TokenType::Punctuation && token.value == ","
+            token.typ == TokenType::Punctuation && token.value() == ","
         }
 
         fn valid(token: Option<&Token>) -> bool {
@@ -393,7 +398,7 @@ impl TokenList {
     fn group_where(&mut self) {
         sub_group!(self, group_where);
         let where_open = (TokenType::Keyword, vec!["WHERE"]);
-        let where_close = (TokenType::Keyword, vec!["ORDER BY", "GROUP BY", "LIMIT", "UNION", "UNION ALL", "EXCEPT", "HAVING", "RETURNING", "INTO"]);
+        let where_close = (TokenType::Keyword, vec!["ORDER BY", "GROUP BY", "LIMIT", "UNION", "UNION ALL", "EXCEPT", "HAVING", "RETURNING", "INTO", "WINDOW", "OFFSET", "FETCH"]);
         let mut tidx = self.token_next_by(&vec![], Some(&where_open), 0);
         while let Some(idx) = tidx {
             let edix = self.token_next_by(&vec![], Some(&where_close), idx+1);
@@ -411,13 +416,13 @@ impl TokenList {
 
         fn valid(token: Option<&Token>) -> bool {
             let types = vec![TokenType::Number, TokenType::NumberInteger, TokenType::NumberFloat, 
-                TokenType::String, TokenType::StringSingle, TokenType::StringSymbol,
+                TokenType::String, TokenType::StringSingle, TokenType::StringSymbol, TokenType::StringDollar,
                 TokenType::Name, TokenType::NamePlaceholder,
                 TokenType::Parenthesis, TokenType::Function, TokenType::Identifier, TokenType::Operation, TokenType::TypedLiteral];
             let patterns = (TokenType::Parenthesis, vec!["(", ")"]);
             if Token::imt(token, &types, Some(&patterns)) {
                 true
-            } else if token.map(|t| t.typ == TokenType::Keyword && t.normalized == "NULL").unwrap_or(false) {
+            } else if token.map(|t| t.typ == TokenType::Keyword && t.normalized() == "NULL").unwrap_or(false) {
                 true
             } else {
                 false
@@ -446,7 +451,7 @@ impl TokenList {
             types.extend(vec![TokenType::SquareBrackets, TokenType::Parenthesis, TokenType::Function, 
                     TokenType::Identifier, TokenType::Operation, TokenType::TypedLiteral]);
             Token::imt(token, &types, None) || 
-                token.map(|t| t.typ == TokenType::Keyword && (t.value == "CURRENT_DATE" || t.value == "CURRENT_TIME" || t.value == "CURRENT_TIMESTAMP")).unwrap_or(false)
+                token.map(|t| t.typ == TokenType::Keyword && (t.value() == "CURRENT_DATE" || t.value() == "CURRENT_TIME" || t.value() == "CURRENT_TIMESTAMP")).unwrap_or(false)
         }
 
         fn post(tlist: &mut TokenList, pidx: usize, tidx: usize, nidx: usize) -> (usize, usize) {
@@ -460,7 +465,7 @@ impl TokenList {
      // schema.table
      fn group_period(&mut self) {
         fn matcher(token: &Token) -> bool {
-            token.typ == TokenType::Punctuation && token.value == "."
+            token.typ == TokenType::Punctuation && token.value() == "."
         }
 
         fn valid_prev(token: Option<&Token>) -> bool {
@@ -485,11 +490,11 @@ impl TokenList {
     fn group_as(&mut self) {
 
         fn matcher(token: &Token) -> bool {
-            token.is_keyword() && token.normalized == "AS"
+            token.is_keyword() && token.normalized() == "AS"
         }
 
         fn valid_prev(token: Option<&Token>) -> bool {
-            token.map(|t| t.normalized == "NULL" || !t.is_keyword()).unwrap_or(false)
+            token.map(|t| t.normalized() == "NULL" || !t.is_keyword()).unwrap_or(false)
         }
 
         fn valid_next(token: Option<&Token>) -> bool {
@@ -507,7 +512,7 @@ impl TokenList {
     fn group_assignment(&mut self) {
 
         fn matcher(token: &Token) -> bool {
-            token.typ == TokenType::Assignment && token.value == ":="
+            token.typ == TokenType::Assignment && token.value() == ":="
         }
 
         fn valid(token: Option<&Token>) -> bool {
@@ -538,14 +543,16 @@ impl TokenList {
         }
     }
 
+    // functions are grouped at every level like the other passes, e.g. in `(f(a) + 1)` and IN lists
     fn group_functions(&mut self) {
+        sub_group!(self, group_functions);
         let mut has_create = false;
         let mut has_table = false;
         for tmp_token in &self.tokens {
-            if tmp_token.normalized == "CREATE" {
+            if tmp_token.normalized() == "CREATE" {
                 has_create = true;
             }
-            if tmp_token.normalized == "TABLE" {
+            if tmp_token.normalized() == "TABLE" {
                 has_table = true;
             }
         }
@@ -558,14 +565,36 @@ impl TokenList {
             let nidx = self.token_next(idx+1, true);
             let next = self.token_idx(nidx);
             if next.map(|n| n.typ == TokenType::Parenthesis).unwrap_or(false) {
-                self.group_tokens(TokenType::Function, idx, nidx.unwrap()+1, false)
+                self.group_tokens(TokenType::Function, idx, nidx.unwrap()+1, false);
+                self.group_over(idx);
             }
             tidx =  self.token_next_by(&ttypes, None, idx+1);
         }
     }
 
-    //  Group together Identifier and Asc/Desc token
+    // OVER (...) or OVER name after the function at idx is moved into the function
+    fn group_over(&mut self, idx: usize) {
+        let oidx = self.token_next(idx+1, true);
+        if !self.token_idx(oidx).map(|t| t.typ == TokenType::Keyword && t.normalized() == "OVER").unwrap_or(false) {
+            return
+        }
+        let oidx = oidx.unwrap();
+        let widx = match self.token_next(oidx+1, true) {
+            Some(widx) => widx,
+            None => return
+        };
+        match self.tokens[widx].typ {
+            TokenType::Parenthesis => window_spec(&mut self.tokens[widx]),
+            TokenType::Name | TokenType::StringSymbol => {},
+            _ => return
+        }
+        self.group_tokens(TokenType::Over, oidx, widx+1, false);
+        self.group_tokens(TokenType::Function, idx, oidx+1, true);
+    }
+
+    //  Group together Identifier and Asc/Desc token, at every level for the ORDER BY of window specifications
     fn group_order(&mut self) {
+        sub_group!(self, group_order);
         let ttypes = vec![TokenType::KeywordOrder];
         let mut tidx = self.token_next_by(&ttypes, None, 0);
         while let Some(idx) = tidx {
@@ -613,6 +642,89 @@ impl TokenList {
         }
     }
 
+    // WITH [RECURSIVE] name [(columns)] AS [[NOT] MATERIALIZED] (query), ...
+    // the tokens up to the main statement are regrouped, the queries keep their grouping
+    fn group_cte(&mut self) {
+        sub_group!(self, group_cte);
+        let mut tidx = self.token_next_by(&[TokenType::KeywordCTE], None, 0);
+        while let Some(idx) = tidx {
+            let end = self.token_next_by(&[TokenType::KeywordDML], None, idx+1).unwrap_or(self.len());
+            let mut leaves = vec![];
+            self.tokens[idx+1..end].iter().for_each(|t| flatten_cte(t, &mut leaves));
+            if let Some((ctes, rest)) = parse_ctes(&leaves) {
+                let mut children = vec![self.tokens[idx].clone()];
+                children.extend(ctes);
+                let with = Token::new_parent(TokenType::With, children);
+                let rest = leaves[rest..].to_vec();
+                self.tokens.splice(idx..end, std::iter::once(with).chain(rest)).for_each(drop);
+            }
+            tidx = self.token_next_by(&[TokenType::KeywordCTE], None, idx+1);
+        }
+    }
+
+    // parentheses whose first token is a DML keyword or WITH
+    fn group_subquery(&mut self) {
+        sub_group!(self, group_subquery);
+        for token in self.tokens.iter_mut().filter(|t| t.typ == TokenType::Parenthesis) {
+            let first = token.children.tokens.iter().skip(1).find(|t| !t.is_whitespace() && !matches!(t.typ, TokenType::Comment | TokenType::CommentSingle | TokenType::CommentMultiline));
+            if first.map(|t| matches!(t.typ, TokenType::KeywordDML | TokenType::KeywordCTE | TokenType::With)).unwrap_or(false) {
+                token.typ = TokenType::Subquery;
+            }
+        }
+    }
+
+    // WINDOW name AS (...), ...
+    fn group_window(&mut self) {
+        sub_group!(self, group_window);
+        for token in self.tokens.iter_mut().filter(|t| t.typ == TokenType::Window) {
+            for definition in token.children.tokens.iter_mut() {
+                match definition.typ {
+                    TokenType::Identifier => window_definition(definition),
+                    TokenType::IdentifierList => definition.children.tokens.iter_mut().for_each(window_definition),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    // FROM, JOIN, GROUP BY, ... up to the next clause of the statement, the trailing whitespace is left out.
+    // clauses are only grouped after a DML keyword of the same level
+    fn group_clauses(&mut self) {
+        sub_group!(self, group_clauses);
+        let mut has_dml = false;
+        let mut prev: Option<usize> = None;
+        let mut idx = 0;
+        while idx < self.len() {
+            let token = &self.tokens[idx];
+            if token.is_whitespace() || is_comment(token) {
+                idx += 1;
+                continue
+            }
+            has_dml |= token.typ == TokenType::KeywordDML;
+            // `IS DISTINCT FROM` is not a clause
+            let after_distinct = prev.map(|p| self.tokens[p].normalized().ends_with("DISTINCT")).unwrap_or(false);
+            let typ = match clause_type(token) {
+                Some(typ) if has_dml && !(typ == TokenType::From && after_distinct) => typ,
+                _ => {
+                    prev = Some(idx);
+                    idx += 1;
+                    continue
+                },
+            };
+            let end = self.token_next_by_fn(is_clause_end, idx+1).unwrap_or(self.len());
+            let last = self.token_matching_fn(|t| !t.is_whitespace() && !is_comment(t), idx, end, true).unwrap_or(idx);
+            self.group_tokens(typ, idx, last+1, false);
+            prev = Some(idx);
+            idx += 1;
+        }
+    }
+
+    /// subqueries of the tokens and of their groups in order of appearance,
+    /// with the nesting depth, 1 for the subqueries not inside another subquery
+    pub fn subqueries(&self) -> impl Iterator<Item = (usize, &Token<'a>)> + '_ {
+        Subqueries { stack: vec![(0, self.tokens.iter())] }
+    }
+
     fn group(&mut self) {
 
         self.group_comments();
@@ -641,9 +753,19 @@ impl TokenList {
         self.align_comments();
         self.group_identifier_list();
         self.group_values();
+        self.group_cte();
+        self.group_subquery();
+        self.group_clauses();
+        self.group_window();
     }
 
-    pub fn get_first_name(&self, idx: Option<usize>, reverse: bool, keywords: bool, real_name: bool) -> Option<&str> {
+    // grouping leaves the lists with the capacity of the ungrouped tokens
+    fn shrink_to_fit(&mut self) {
+        self.tokens.shrink_to_fit();
+        self.tokens.iter_mut().filter(|t| t.is_group()).for_each(|t| t.children.shrink_to_fit());
+    }
+
+    pub fn get_first_name(&self, idx: Option<usize>, reverse: bool, keywords: bool, real_name: bool) -> Option<Cow<'_, str>> {
         let idx = idx.unwrap_or(0);
         let tokens = &self.tokens[idx..];
         let mut ttypes = vec![TokenType::Name, TokenType::Wildcard, TokenType::StringSymbol];
@@ -653,7 +775,7 @@ impl TokenList {
         if reverse {
             for token in tokens.iter().rev() {
                 if ttypes.iter().find(|typ| **typ == token.typ).is_some() {
-                    return Some(remove_quotes(&token.value))
+                    return Some(unquote_name(token.value()))
                 } else if token.typ == TokenType::Identifier || token.typ == TokenType::Function {
                     return if real_name { token.get_real_name() } else { token.get_name() }
                 }         
@@ -661,7 +783,7 @@ impl TokenList {
         }
         for token in tokens {
             if ttypes.iter().find(|typ| **typ == token.typ).is_some() {
-                return Some(remove_quotes(&token.value))
+                return Some(unquote_name(token.value()))
             } else if token.typ == TokenType::Identifier || token.typ == TokenType::Function {
                 return if real_name { token.get_real_name() } else { token.get_name() }
             }         
@@ -671,6 +793,98 @@ impl TokenList {
 
 }
 
+fn is_comment(token: &Token) -> bool {
+    matches!(token.typ, TokenType::Comment | TokenType::CommentSingle | TokenType::CommentMultiline)
+}
+
+// frame words are names to the lexer, and identifiers once a named window is grouped
+fn window_spec(token: &mut Token) {
+    token.typ = TokenType::WindowSpec;
+    for t in token.children.tokens.iter_mut() {
+        if t.typ == TokenType::Identifier && t.children.len() == 1 && is_frame_word(&t.children.tokens[0]) {
+            *t = t.children.tokens[0].clone();
+        }
+        if is_frame_word(t) {
+            t.typ = TokenType::Keyword;
+        }
+    }
+}
+
+fn is_frame_word(token: &Token) -> bool {
+    token.typ == TokenType::Name &&
+        matches!(token.value().to_uppercase().as_str(), "GROUPS" | "UNBOUNDED" | "PRECEDING" | "FOLLOWING" | "EXCLUDE" | "TIES" | "OTHERS")
+}
+
+fn window_definition(token: &mut Token) {
+    if token.typ != TokenType::Identifier {
+        return
+    }
+    let children = &mut token.children.tokens;
+    let is_definition = children.iter().rev().filter(|t| !t.is_whitespace()).nth(1).map(|t| t.normalized() == "AS").unwrap_or(false);
+    if let Some(last) = children.last_mut().filter(|t| t.typ == TokenType::Parenthesis && is_definition) {
+        window_spec(last);
+    }
+}
+
+// group type of a clause starting with the keyword
+fn clause_type(token: &Token) -> Option<TokenType> {
+    if token.typ != TokenType::Keyword {
+        return None
+    }
+    let word = token.normalized().split_whitespace().collect::<Vec<_>>().join(" ");
+    let typ = match word.as_str() {
+        "FROM" => TokenType::From,
+        "GROUP BY" => TokenType::GroupBy,
+        "HAVING" => TokenType::Having,
+        "ORDER BY" => TokenType::OrderBy,
+        "LIMIT" => TokenType::Limit,
+        "OFFSET" => TokenType::Offset,
+        "FETCH" => TokenType::Fetch,
+        "WINDOW" => TokenType::Window,
+        "RETURNING" => TokenType::Returning,
+        _ if word.ends_with("JOIN") => TokenType::Join,
+        _ => return None,
+    };
+    Some(typ)
+}
+
+// the next clause, WHERE, a set operator, another statement, `;` or the closing parenthesis ends a clause
+fn is_clause_end(token: &Token) -> bool {
+    clause_type(token).is_some() || matches!(token.typ, TokenType::Where | TokenType::KeywordDML) ||
+        (token.typ == TokenType::Punctuation && (token.value() == ";" || token.value() == ")")) ||
+        (token.typ == TokenType::Keyword && ["UNION", "INTERSECT", "EXCEPT", "MINUS", "INTO"].iter().any(|w| token.normalized().starts_with(w)))
+}
+
+struct Subqueries<'t, 'a> {
+    // tokens left at each level, with the depth of the subqueries found among them
+    stack: Vec<(usize, std::slice::Iter<'t, Token<'a>>)>,
+}
+
+impl<'t, 'a> Iterator for Subqueries<'t, 'a> {
+    type Item = (usize, &'t Token<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((depth, tokens)) = self.stack.last_mut() {
+            let depth = *depth;
+            let token = match tokens.next() {
+                Some(token) => token,
+                None => {
+                    self.stack.pop();
+                    continue
+                },
+            };
+            if token.typ == TokenType::Subquery {
+                self.stack.push((depth+1, token.children.tokens.iter()));
+                return Some((depth+1, token))
+            }
+            if token.is_group() {
+                self.stack.push((depth, token.children.tokens.iter()));
+            }
+        }
+        None
+    }
+}
+
 fn group_matching(tlist: &mut TokenList, typ: &TokenType, open: &[&str], close: &str) {
     // Groups Tokens that have beginning and end.
     let mut opens = vec![];
@@ -691,9 +905,9 @@ fn group_matching(tlist: &mut TokenList, typ: &TokenType, open: &[&str], close:
             continue
         }
         idx += 1;
-        if open.contains(&token.normalized.as_str()) {
+        if open.contains(&token.normalized()) {
             opens.push(tidx);
-        } else if token.normalized == close {
+        } else if token.normalized() == close {
             if opens.len() < 1 {
                 continue
             }
@@ -706,6 +920,83 @@ fn group_matching(tlist: &mut TokenList, typ: &TokenType, open: &[&str], close:
     }
 }
 
+// leaf tokens of the common table expressions, the parentheses keep their grouping
+fn flatten_cte<'a>(token: &Token<'a>, leaves: &mut Vec<Token<'a>>) {
+    match token.typ {
+        TokenType::Parenthesis | TokenType::Comment => leaves.push(token.clone()),
+        _ if token.is_group() => token.children.tokens.iter().for_each(|t| flatten_cte(t, leaves)),
+        _ => leaves.push(token.clone()),
+    }
+}
+
+// tokens of a WITH group after the keyword and the index of the first leaf left after it
+fn parse_ctes<'a>(leaves: &[Token<'a>]) -> Option<(Vec<Token<'a>>, usize)> {
+    let skipped = |t: &Token| t.is_whitespace() || t.typ == TokenType::Comment || t.typ == TokenType::CommentSingle || t.typ == TokenType::CommentMultiline;
+    let next = |from: usize| leaves[from..].iter().position(|t| !skipped(t)).map(|i| from+i);
+    let mut children = vec![];
+    let mut idx = next(0)?;
+    if leaves[idx].normalized() == "RECURSIVE" {
+        idx = next(idx+1)?;
+    }
+    children.extend(leaves[..idx].iter().cloned());
+    let mut list = vec![];
+    loop {
+        // name [(columns)]
+        let name = &leaves[idx];
+        if !matches!(name.typ, TokenType::Name | TokenType::StringSymbol | TokenType::Keyword) || name.normalized() == "AS" {
+            return None
+        }
+        let mut cte = vec![Token::new_parent(TokenType::Identifier, vec![name.clone()])];
+        let mut start = idx+1;
+        let mut pos = next(start)?;
+        if leaves[pos].typ == TokenType::Parenthesis {
+            let mut head = cte;
+            head.extend(leaves[start..=pos].iter().cloned());
+            cte = vec![Token::new_parent(TokenType::Function, head)];
+            start = pos+1;
+            pos = next(start)?;
+        }
+        if leaves[pos].normalized() != "AS" {
+            return None
+        }
+        let mut body = next(pos+1)?;
+        for marker in ["NOT", "MATERIALIZED"] {
+            if leaves[body].value().eq_ignore_ascii_case(marker) {
+                body = next(body+1)?;
+            }
+        }
+        if leaves[body].typ != TokenType::Parenthesis {
+            return None
+        }
+        for token in &leaves[start..=body] {
+            let mut token = token.clone();
+            if token.typ == TokenType::Name && token.value().eq_ignore_ascii_case("MATERIALIZED") {
+                token.typ = TokenType::Keyword;
+            }
+            cte.push(token);
+        }
+        list.push(Token::new_parent(TokenType::CommonTableExpression, cte));
+        match next(body+1) {
+            Some(comma) if leaves[comma].typ == TokenType::Punctuation && leaves[comma].value() == "," => {
+                idx = next(comma+1)?;
+                list.extend(leaves[body+1..idx].iter().cloned());
+            },
+            rest => {
+                // only whitespace and comments may be left before the main statement
+                if rest.is_some() {
+                    return None
+                }
+                if list.len() > 1 {
+                    children.push(Token::new_parent(TokenType::IdentifierList, list));
+                } else {
+                    children.extend(list);
+                }
+                return Some((children, body+1))
+            },
+        }
+    }
+}
+
 // TODO: interface Grouping
 fn group_internal(
         tlist: &mut TokenList, 
@@ -814,7 +1105,7 @@ mod tests {
         let sql = "select * from users where id > 0;";
         let mut token_list = TokenList::from(sql);
         token_list.group();
-        assert_eq!(token_list.tokens[8].typ, TokenType::Where);
+        assert_eq!(token_list.tokens[6].typ, TokenType::Where);
     }
 
     #[test]
@@ -823,7 +1114,7 @@ mod tests {
         let token_list = TokenList::from(sql);
         let t = token_list.token_prev(token_list.len(), true);
         let t = token_list.token_idx(t).unwrap();
-        assert_eq!(t.value, "from");
+        assert_eq!(t.value(), "from");
     }
 
     #[test]
@@ -842,20 +1133,20 @@ mod tests {
         let mut token_list = TokenList::from(sql);
         token_list.group_period();
         assert_eq!(token_list.tokens[6].typ, TokenType::Identifier);
-        assert_eq!(token_list.tokens[6].value, "sch.account");
+        assert_eq!(token_list.tokens[6].value(), "sch.account");
 
         let sql = "select * from sch.user";
         let mut token_list = TokenList::from(sql);
         token_list.group_period();
         assert_eq!(token_list.tokens[6].typ, TokenType::Identifier);
-        assert_eq!(token_list.tokens[6].value, "sch.user");
+        assert_eq!(token_list.tokens[6].value(), "sch.user");
 
         let sql = "select * from sch.user as u";
         let mut token_list = TokenList::from(sql);
         token_list.group_period();
         token_list.group_as();
         assert_eq!(token_list.tokens[6].typ, TokenType::Identifier);
-        assert_eq!(token_list.tokens[6].value, "sch.user as u");
+        assert_eq!(token_list.tokens[6].value(), "sch.user as u");
     }
 
     #[test]
@@ -863,8 +1154,10 @@ mod tests {
         let sql = "select * from users order by id desc";
         let mut token_list = TokenList::from(sql);
         token_list.group();
-        assert_eq!(token_list.tokens[10].typ, TokenType::Identifier);
-        assert_eq!(token_list.tokens[10].value, "id desc");
+        assert_eq!(token_list.tokens[6].typ, TokenType::OrderBy);
+        let order = &token_list.tokens[6].children;
+        assert_eq!(order.tokens[2].typ, TokenType::Identifier);
+        assert_eq!(order.tokens[2].value(), "id desc");
     }
 
     #[test]
@@ -872,32 +1165,32 @@ mod tests {
         let sql = "select * from test.person as p where ";
         let mut token_list = TokenList::from(sql);
         token_list.group();
-        let id = token_list.token_idx(Some(6)).unwrap();
+        let id = &token_list.tokens[4].children.tokens[2];
         let real_name = id.get_real_name();
         let parent_name = id.get_parent_name();
         let alias = id.get_alias();
-        assert_eq!(real_name, Some("person"));
-        assert_eq!(parent_name, Some("test"));
-        assert_eq!(alias, Some("p"));
+        assert_eq!(real_name.as_deref(), Some("person"));
+        assert_eq!(parent_name.as_deref(), Some("test"));
+        assert_eq!(alias.as_deref(), Some("p"));
 
         let sql = "select * from test.person where ";
         let mut token_list = TokenList::from(sql);
         token_list.group();
-        let id = token_list.token_idx(Some(6)).unwrap();
+        let id = &token_list.tokens[4].children.tokens[2];
         let real_name = id.get_real_name();
         let parent_name = id.get_parent_name();
         let alias = id.get_alias();
-        assert_eq!(real_name, Some("person"));
-        assert_eq!(parent_name, Some("test"));
+        assert_eq!(real_name.as_deref(), Some("person"));
+        assert_eq!(parent_name.as_deref(), Some("test"));
         assert_eq!(alias, None);
 
         let sql = "select * from person where ";
         let mut token_list = TokenList::from(sql);
         token_list.group();
-        let id = token_list.token_idx(Some(6)).unwrap();
+        let id = &token_list.tokens[4].children.tokens[2];
         let real_name = id.get_real_name();
         let parent_name = id.get_parent_name();
-        assert_eq!(real_name, Some("person"));
+        assert_eq!(real_name.as_deref(), Some("person"));
         assert_eq!(parent_name, None);
     }
 
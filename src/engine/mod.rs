@@ -1,6 +1,8 @@
 pub mod filter_stack;
 pub mod grouping;
 pub mod splitter;
+pub mod stream;
 
 pub use filter_stack::FilterStack;
-pub use splitter::StatementSplitter;
\ No newline at end of file
+pub use splitter::{StatementSplitter, StatementSlice};
+pub use stream::StatementStream;
\ No newline at end of file
@@ -1,12 +1,54 @@
-use std::cell::Cell;
-use crate::lexer::{Token};
+use std::cell::{Cell, RefCell};
+use std::ops::{Range, RangeInclusive};
+use crate::lexer::{Token, advance_position, delimiter_command_arg};
 use crate::tokens::TokenType;
+use crate::dialect::Dialect;
 
 const EOS_TTYPE: [TokenType; 2] = [TokenType::Whitespace, TokenType::CommentSingle];
 
-#[derive(Default)]
+/// a statement of a sql script, borrowed from the source sql
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatementSlice<'a> {
+    /// text of the statement without leading and trailing whitespace,
+    /// comments after the terminator on the same line are included
+    pub text: &'a str,
+    /// byte range of the text in the source sql
+    pub range: Range<usize>,
+    /// lines of the first and last characters, starts from 1
+    pub lines: RangeInclusive<usize>,
+    /// the terminator or block terminator ending the statement, a batch separator like `GO` is a
+    /// statement of its own with itself as terminator, so the statement before it has `None`
+    pub terminator: Option<&'a str>,
+}
+
+impl<'a> StatementSlice<'a> {
+
+    // None if the statement has only whitespace
+    pub(crate) fn new(sql: &'a str, tokens: &[Token], terminator: Option<usize>) -> Option<Self> {
+        let first = tokens.iter().find(|t| !t.is_whitespace())?;
+        let last = tokens.iter().rev().find(|t| !t.is_whitespace())?;
+        let text = sql[first.span.start..last.span.end].trim_end();
+        let (end_line, _) = advance_position(text, first.span.line, first.span.column);
+        Some(Self {
+            text,
+            range: first.span.start..first.span.start+text.len(),
+            lines: first.span.line..=end_line,
+            terminator: terminator.map(|idx| &sql[tokens[idx].span.start..tokens[idx].span.end]),
+        })
+    }
+}
+
 pub struct StatementSplitter {
+    terminators: Vec<&'static str>,
+    batch_separator: Option<&'static str>,
+    block_terminator: Option<&'static str>,
+    delimiter_command: bool,
+    // terminator set by the DELIMITER command
+    delimiter: RefCell<Option<String>>,
     in_declare: Cell<bool>,
+    in_block: Cell<bool>,
+    // count of leading words checked for a PL/SQL block
+    head_words: Cell<usize>,
     is_create: Cell<bool>,
     consume_ws: Cell<bool>,
     begin_depth: Cell<usize>,
@@ -14,10 +56,35 @@ pub struct StatementSplitter {
     // tokens: Vec<Token>
 }
 
+impl Default for StatementSplitter {
+    fn default() -> Self {
+        Self::new(&crate::dialect::GenericDialect)
+    }
+}
+
 impl StatementSplitter {
 
+    pub fn new(dialect: &dyn Dialect) -> Self {
+        Self {
+            terminators: dialect.statement_terminators().to_vec(),
+            batch_separator: dialect.batch_separator(),
+            block_terminator: dialect.block_terminator(),
+            delimiter_command: dialect.delimiter_command(),
+            delimiter: RefCell::default(),
+            in_declare: Cell::default(),
+            in_block: Cell::default(),
+            head_words: Cell::default(),
+            is_create: Cell::default(),
+            consume_ws: Cell::default(),
+            begin_depth: Cell::default(),
+            level: Cell::default(),
+        }
+    }
+
     fn reset(&self) {
         self.in_declare.set(false);
+        self.in_block.set(false);
+        self.head_words.set(0);
         self.is_create.set(false);
         self.consume_ws.set(false);
         self.begin_depth.set(0);
@@ -36,15 +103,15 @@ impl StatementSplitter {
     }
 
     fn change_splitlevel(&self, token: &Token) -> isize {
-        if token.typ == TokenType::Punctuation && token.value == "(" {
+        if token.typ == TokenType::Punctuation && token.value() == "(" {
             return 1;
-        } else if token.typ == TokenType::Punctuation && token.value == ")" {
+        } else if token.typ == TokenType::Punctuation && token.value() == ")" {
             return -1;
         } else if !token.is_keyword() {
             return 0;
         }
 
-        let unified = token.value.to_uppercase();
+        let unified = token.value().to_uppercase();
         if token.typ == TokenType::KeywordDDL && unified.starts_with("CREATE") {
             self.is_create.set(true);
             return 0
@@ -76,31 +143,196 @@ impl StatementSplitter {
         0
     }
 
-    pub fn process(&self, tokens: Vec<Token>) -> Vec<Vec<Token>> {
+    // indexes of batch separators standing alone on their line
+    fn batch_separators(&self, tokens: &[Token], line_start: bool) -> Vec<usize> {
+        match self.batch_separator {
+            Some(separator) => standalone_lines(tokens, separator, line_start),
+            None => vec![],
+        }
+    }
+
+    // indexes of block terminators standing alone on their line
+    fn block_terminators(&self, tokens: &[Token], line_start: bool) -> Vec<usize> {
+        match self.block_terminator {
+            Some(terminator) => standalone_lines(tokens, terminator, line_start),
+            None => vec![],
+        }
+    }
+
+    // PL/SQL blocks start with DECLARE, BEGIN or CREATE PACKAGE, PROCEDURE, FUNCTION, TRIGGER, TYPE
+    fn update_block(&self, tokens: &[Token], token: &Token) {
+        if self.block_terminator.is_none() || self.in_block.get() || token.is_whitespace() || is_comment(token) {
+            return
+        }
+        let head_words = self.head_words.get() + 1;
+        self.head_words.set(head_words);
+        if head_words > 5 {
+            return
+        }
+        let mut words = tokens.iter().chain(std::iter::once(token))
+            .filter(|t| !t.is_whitespace() && !is_comment(t))
+            .map(|t| t.value().to_uppercase());
+        let in_block = match words.next().as_deref() {
+            Some("DECLARE") | Some("BEGIN") => true,
+            Some(w) if w.starts_with("CREATE") => words
+                .find(|w| !matches!(w.as_str(), "OR" | "REPLACE" | "EDITIONABLE" | "NONEDITIONABLE"))
+                .map(|w| matches!(w.as_str(), "PACKAGE" | "PROCEDURE" | "FUNCTION" | "TRIGGER" | "TYPE"))
+                .unwrap_or(false),
+            _ => false,
+        };
+        self.in_block.set(in_block);
+    }
+
+    // indexes of DELIMITER commands filling their line and of their arguments,
+    // a command must also start a statement outside any parenthesis
+    fn delimiter_commands(&self, tokens: &[Token], mut line_start: bool) -> Vec<(usize, usize)> {
+        if !self.delimiter_command {
+            return vec![]
+        }
+        let mut indexes = vec![];
+        for (idx, token) in tokens.iter().enumerate() {
+            if line_start {
+                if let Some(arg) = delimiter_command_arg(&tokens[idx..]) {
+                    indexes.push((idx, idx+arg));
+                }
+            }
+            line_start = match token.typ {
+                TokenType::Newline => true,
+                TokenType::Whitespace => line_start,
+                TokenType::CommentSingle => token.value().ends_with('\n') || token.value().ends_with('\r'),
+                _ => false,
+            };
+        }
+        indexes
+    }
+
+    fn is_terminator(&self, token: &Token) -> bool {
+        if token.typ != TokenType::Punctuation {
+            return false
+        }
+        match &*self.delimiter.borrow() {
+            Some(delimiter) => token.value() == *delimiter,
+            None => !self.in_block.get() && self.level.get() <= 0 && self.terminators.contains(&token.value()),
+        }
+    }
+
+    pub fn process<'a>(&self, tokens: Vec<Token<'a>>) -> Vec<Vec<Token<'a>>> {
+        self.process_resume(tokens, None, true)
+    }
+
+    /// split tokens lexed from a statement boundary, with the terminator set by an earlier
+    /// `DELIMITER` command and whether the tokens start a line
+    pub(crate) fn process_resume<'a>(&self, tokens: Vec<Token<'a>>, delimiter: Option<String>, line_start: bool) -> Vec<Vec<Token<'a>>> {
+        self.split_statements(tokens, delimiter, line_start).into_iter().map(|(stmt, _)| stmt).collect()
+    }
+
+    // statements with the index of the token ending them, a terminator, a block terminator or a batch separator
+    pub(crate) fn split_statements<'a>(&self, tokens: Vec<Token<'a>>, delimiter: Option<String>, line_start: bool) -> Vec<(Vec<Token<'a>>, Option<usize>)> {
         let mut stmts = vec![];
-        let mut tmp_tokens = vec![];
-        for token in tokens.into_iter() {
+        let mut tmp_tokens: Vec<Token<'a>> = vec![];
+        let mut terminator = None;
+        // indexes are sorted, lookups stay logarithmic on large scripts
+        let separators = self.batch_separators(&tokens, line_start);
+        let commands = self.delimiter_commands(&tokens, line_start);
+        let mut command_arg = None;
+        let block_terminators = self.block_terminators(&tokens, line_start);
+        self.reset();
+        self.delimiter.replace(delimiter);
+        for (idx, token) in tokens.into_iter().enumerate() {
             if self.consume_ws.get() && !EOS_TTYPE.contains(&token.typ) {
                 let stmt_tokens = std::mem::replace(&mut tmp_tokens, vec![]);
-                stmts.push(stmt_tokens);
+                stmts.push((stmt_tokens, terminator.take()));
+                self.reset();
+            }
+
+            // the batch separator is a statement on its own
+            if separators.binary_search(&idx).is_ok() {
+                if tmp_tokens.iter().any(|t| !t.is_whitespace()) {
+                    stmts.push((std::mem::take(&mut tmp_tokens), None));
+                }
                 self.reset();
+                self.consume_ws.set(true);
+                terminator = Some(tmp_tokens.len());
+                tmp_tokens.push(token);
+                continue
             }
 
+            // the DELIMITER command is a statement on its own, its argument replaces the terminators
+            let stmt_start = self.level.get() <= 0 && tmp_tokens.iter().all(|t| t.is_whitespace() || is_comment(t));
+            if let Some(pos) = commands.binary_search_by_key(&idx, |(i, _)| *i).ok().filter(|_| stmt_start) {
+                if tmp_tokens.iter().any(|t| !t.is_whitespace()) {
+                    stmts.push((std::mem::take(&mut tmp_tokens), None));
+                }
+                self.reset();
+                command_arg = Some(commands[pos].1);
+                tmp_tokens.push(token);
+                continue
+            }
+            if command_arg == Some(idx) {
+                command_arg = None;
+                self.delimiter.replace(if token.value() == ";" { None } else { Some(token.value().to_string()) });
+                self.consume_ws.set(true);
+                tmp_tokens.push(token);
+                continue
+            }
+
+            // the block terminator ends the current statement whatever the level
+            if block_terminators.binary_search(&idx).is_ok() {
+                self.consume_ws.set(true);
+                terminator = Some(tmp_tokens.len());
+                tmp_tokens.push(token);
+                continue
+            }
+
+            self.update_block(&tmp_tokens, &token);
             let level = self.level.get() + self.change_splitlevel(&token);
             self.level.set(level);
-            if self.level.get() <= 0 && token.typ == TokenType::Punctuation && token.value == ";"  {
-                self.consume_ws.set(true)
+            if self.is_terminator(&token) {
+                self.consume_ws.set(true);
+                terminator = Some(tmp_tokens.len());
             }
             tmp_tokens.push(token);
         }
-        if tmp_tokens.len() > 0 && tmp_tokens.iter().find(|t| t.typ != TokenType::Whitespace).is_some() {
+        if tmp_tokens.len() > 0 && tmp_tokens.iter().find(|t| !t.is_whitespace()).is_some() {
             let stmt_tokens = std::mem::replace(&mut tmp_tokens, vec![]);
-            stmts.push(stmt_tokens);
+            stmts.push((stmt_tokens, terminator));
         }
         stmts
     }
 }
 
+fn standalone_lines(tokens: &[Token], value: &str, mut line_start: bool) -> Vec<usize> {
+    let mut indexes = vec![];
+    for (idx, token) in tokens.iter().enumerate() {
+        if line_start && token.value().eq_ignore_ascii_case(value) && is_line_end(&tokens[idx+1..]) {
+            indexes.push(idx);
+        }
+        line_start = match token.typ {
+            TokenType::Newline => true,
+            TokenType::Whitespace => line_start,
+            TokenType::CommentSingle => token.value().ends_with('\n') || token.value().ends_with('\r'),
+            _ => false,
+        };
+    }
+    indexes
+}
+
+fn is_comment(token: &Token) -> bool {
+    matches!(token.typ, TokenType::Comment | TokenType::CommentSingle | TokenType::CommentMultiline)
+}
+
+// only whitespace or a comment follows until the end of line
+fn is_line_end(tokens: &[Token]) -> bool {
+    for token in tokens {
+        match token.typ {
+            TokenType::Whitespace => continue,
+            TokenType::Newline | TokenType::CommentSingle => return true,
+            _ => return false,
+        }
+    }
+    true
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
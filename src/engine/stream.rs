@@ -0,0 +1,88 @@
+use std::collections::VecDeque;
+use std::io::{self, BufRead};
+use crate::lexer::{Token, Resume};
+use super::FilterStack;
+
+// bytes read at least before the buffered text is lexed again
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// statements of a script read from a `BufRead`, yielded one at a time.
+/// Only the text of the statements not yielded yet is kept in memory,
+/// tokens own their text.
+pub struct StatementStream<'s, R> {
+    stack: &'s FilterStack,
+    reader: R,
+    grouping: bool,
+    chunk_size: usize,
+    // text from the first statement not split yet
+    buf: String,
+    // length of buf when it was last lexed
+    lexed: usize,
+    resume: Resume,
+    stmts: VecDeque<Vec<Token<'static>>>,
+    eof: bool,
+}
+
+impl<'s, R: BufRead> StatementStream<'s, R> {
+
+    pub(crate) fn new(stack: &'s FilterStack, reader: R, grouping: bool) -> Self {
+        Self { stack, reader, grouping, chunk_size: CHUNK_SIZE, buf: String::new(), lexed: 0, resume: Resume::default(), stmts: VecDeque::new(), eof: false }
+    }
+
+    /// bytes read at least before the buffered text is lexed again, 64 KiB by default
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    // read whole lines until the buffer doubled since it was last lexed,
+    // a long statement is not lexed again for every line
+    fn fill(&mut self) -> io::Result<()> {
+        let target = self.lexed + self.lexed.max(self.chunk_size);
+        while self.buf.len() < target {
+            if self.reader.read_line(&mut self.buf)? == 0 {
+                self.eof = true;
+                break
+            }
+        }
+        Ok(())
+    }
+
+    // queue the statements known to be complete, the last one may continue on the next line
+    // and a quote or comment left open may be closed by the text not read yet
+    fn split(&mut self) {
+        let (mut stmts, unclosed) = self.stack.split_resume(&self.buf, &self.resume);
+        let mut complete = if self.eof { stmts.len() } else { stmts.len().saturating_sub(1) };
+        if let Some(offset) = unclosed.filter(|_| !self.eof) {
+            complete = stmts[..complete].iter().take_while(|stmt| stmt.last().map(|t| t.span.end <= offset).unwrap_or(true)).count();
+        }
+        stmts.truncate(complete);
+        let start = self.resume.offset;
+        for stmt in stmts {
+            self.resume.advance(&stmt);
+            self.stmts.push_back(stmt.into_iter().map(Token::into_owned).collect());
+        }
+        if self.eof {
+            self.buf.clear();
+        } else {
+            self.buf.drain(..self.resume.offset - start);
+        }
+        self.lexed = self.buf.len();
+    }
+}
+
+impl<R: BufRead> Iterator for StatementStream<'_, R> {
+    type Item = io::Result<Vec<Token<'static>>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.stmts.is_empty() && !self.eof {
+            if let Err(e) = self.fill() {
+                self.eof = true;
+                return Some(Err(e))
+            }
+            self.split();
+        }
+        let tokens = self.stmts.pop_front()?;
+        Some(Ok(if self.grouping { super::grouping::group(tokens) } else { tokens }))
+    }
+}
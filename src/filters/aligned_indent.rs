@@ -32,7 +32,7 @@ impl AlignedIndentFilter {
         }
     }
 
-    fn nl(&self, offset: isize) -> Token {
+    fn nl(&self, offset: isize) -> Token<'static> {
         let indent = self.indent * (2 + self.max_kwd_len);
         let i = (self.max_kwd_len + indent + self.offset) as isize + offset;
         let i = if i > 0 { i as usize } else { 0 };
@@ -45,10 +45,10 @@ impl AlignedIndentFilter {
         while let Some(idx) = tidx {
             let token = token_list.token_idx(Some(idx)).unwrap();
             let token_indent = if token.is_keyword() && 
-                (token.normalized.ends_with("JOIN") ||  token.normalized.ends_with("BY")) {
-                token.normalized.split_whitespace().next().map(|s| s.len()).unwrap()
+                (token.normalized().ends_with("JOIN") ||  token.normalized().ends_with("BY")) {
+                token.normalized().split_whitespace().next().map(|s| s.len()).unwrap()
             } else {
-                token.value.len()
+                token.value().len()
             };
             let nl = self.nl(0 - token_indent as isize);
             let forward = if token_list.insert_newline_before(idx, nl) { 1 } else { 2 };
@@ -63,8 +63,9 @@ impl AlignedIndentFilter {
     fn process_internal(&mut self, token_list: &mut TokenList, token_type: &TokenType) {
         match token_type {
             TokenType::IdentifierList => self.process_identifierlist(token_list),
-            TokenType::Parenthesis => self.process_parenthesis(token_list),
+            TokenType::Parenthesis | TokenType::Subquery => self.process_parenthesis(token_list),
             TokenType::Case => self.process_case(token_list),
+            TokenType::WindowSpec => token_list.tokens.iter().for_each(|t| self.prev_sql.push_str(t.value())),
             _ => self.process_default(token_list),
         }
     }
@@ -106,7 +107,7 @@ impl AlignedIndentFilter {
         }
 
         let condition_width = cases.iter()
-                .map(|c| c.0.iter().map(|idx| token_list.tokens[*idx].value.as_str()).collect::<Vec<_>>().join(" ").len())
+                .map(|c| c.0.iter().map(|idx| token_list.tokens[*idx].value()).collect::<Vec<_>>().join(" ").len())
                 .collect::<Vec<_>>();
 
         // align THEN position
@@ -116,7 +117,7 @@ impl AlignedIndentFilter {
         for (idx, (cond, value)) in cases.iter().enumerate() {
             let token_idx = if cond.len() > 0 {cond[0]} else {value[0]};
             if idx > 0 {
-                let token_len = token_list.tokens[token_idx].value.len();
+                let token_len = token_list.tokens[token_idx].value().len();
                 let offset = offset_ as isize - token_len as isize;
                 if !token_list.insert_newline_before(token_idx, self.nl(offset)) {
                     insert_count += 1;
@@ -151,7 +152,7 @@ impl AlignedIndentFilter {
                 // }
                 self.offset -= offset;
             } else {
-                self.prev_sql.push_str(&token.value)
+                self.prev_sql.push_str(token.value())
             }
         }
         // remove_indexes.iter().enumerate().for_each(|(i, idx)| {token_list.tokens.remove(idx-i);});
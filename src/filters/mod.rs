@@ -23,12 +23,12 @@ pub trait TokenListFilter: Send+Sync {
     fn process(&mut self, token_list: &mut TokenList);
 }
 
-const SPLIT_WORDS: [&str; 12] = ["FROM", "AND", "OR", "GROUP BY", 
-    "ORDER BY", "UNION", "VALUES", "SET", "BETWEEN", "EXCEPT", "HAVING", "LIMIT"];
+const SPLIT_WORDS: [&str; 13] = ["FROM", "AND", "OR", "GROUP BY", 
+    "ORDER BY", "UNION", "VALUES", "SET", "BETWEEN", "EXCEPT", "HAVING", "WINDOW", "LIMIT"];
 
-const SPLIT_WORDS_ALIGN: [&str; 14] = ["FROM", "ON", "WHERE", "AND", "OR",
+const SPLIT_WORDS_ALIGN: [&str; 15] = ["FROM", "ON", "WHERE", "AND", "OR",
 "GROUP BY", "ORDER BY","UNION", "VALUES", 
-"SET", "BETWEEN", "EXCEPT", "HAVING", "LIMIT"];
+"SET", "BETWEEN", "EXCEPT", "HAVING", "WINDOW", "LIMIT"];
 
 fn next_token(token_list: &TokenList, idx: usize) -> Option<usize> {
     next_token_internal(token_list, idx, &SPLIT_WORDS)
@@ -40,14 +40,14 @@ fn next_token_align(token_list: &TokenList, idx: usize) -> Option<usize> {
 
 fn next_token_internal(token_list: &TokenList, idx: usize, split_words: &[&str]) -> Option<usize> {
     let mut tidx = token_list.token_next_by_fn(|t| match t.typ {
-        TokenType::Keyword => split_words.iter().find(|w| **w == t.normalized).is_some() || t.normalized.ends_with("STRAIGHT_JOIN") || t.normalized.ends_with("JOIN"),
+        TokenType::Keyword => split_words.iter().find(|w| **w == t.normalized()).is_some() || t.normalized().ends_with("STRAIGHT_JOIN") || t.normalized().ends_with("JOIN"),
         _ => false
     } , idx);
     let token = token_list.token_idx(tidx);
-    if token.map(|t| t.normalized == "BETWEEN").unwrap_or(false) {
+    if token.map(|t| t.normalized() == "BETWEEN").unwrap_or(false) {
         tidx = next_token_internal(token_list, tidx.unwrap()+1, split_words);
         let token = token_list.token_idx(tidx);
-        if token.map(|t| t.normalized == "AND").unwrap_or(false) {
+        if token.map(|t| t.normalized() == "AND").unwrap_or(false) {
             tidx = next_token_internal(token_list, tidx.unwrap()+1, split_words);
         } 
     }
@@ -2,6 +2,7 @@ use regex::Regex;
 use super::{StmtFilter, TokenListFilter};
 use crate::lexer::{Token, TokenList};
 use crate::tokens::{TokenType};
+use crate::visitor::{Visit, VisitContext, VisitorMut};
 
 pub struct StripCommentsFilter {
     newline_reg: Regex,
@@ -20,11 +21,11 @@ impl StripCommentsFilter {
         token_list.token_next_by(&ttypes, None, start)
     }
 
-    fn get_insert_token(&self, token: &Token) -> Token {
-        let caps = self.newline_reg.captures(&token.value);
+    fn get_insert_token(&self, token: &Token) -> Token<'static> {
+        let caps = self.newline_reg.captures(token.value());
         if let Some(caps) = caps {
             if let Some(cap) = caps.get(1).map(|c| c.as_str()) {
-                return Token::new(TokenType::Newline, cap)
+                return Token::new(TokenType::Newline, cap.to_string())
             } 
         }
         Token::new(TokenType::Whitespace, " ")
@@ -41,9 +42,9 @@ impl StripCommentsFilter {
             let insert_token = self.get_insert_token(token);
             let mut step: isize = 0;
             if ptoken.is_none() || ntoken.is_none() ||
-                 ptoken.map(|p| p.is_whitespace()).unwrap_or(false) || ptoken.map(|p| p.typ == TokenType::Punctuation && p.value == "(").unwrap_or(false) ||
-                 ntoken.map(|p| p.is_whitespace()).unwrap_or(false) || ntoken.map(|p| p.typ == TokenType::Punctuation && p.value == ")").unwrap_or(false)  {
-                    if ptoken.is_some() && !ptoken.map(|p| p.typ == TokenType::Punctuation && p.value == "(").unwrap() {
+                 ptoken.map(|p| p.is_whitespace()).unwrap_or(false) || ptoken.map(|p| p.typ == TokenType::Punctuation && p.value() == "(").unwrap_or(false) ||
+                 ntoken.map(|p| p.is_whitespace()).unwrap_or(false) || ntoken.map(|p| p.typ == TokenType::Punctuation && p.value() == ")").unwrap_or(false)  {
+                    if ptoken.is_some() && !ptoken.map(|p| p.typ == TokenType::Punctuation && p.value() == "(").unwrap() {
                         token_list.insert_after(idx, insert_token, false);
                         step += 1;
                     }
@@ -60,16 +61,25 @@ impl StripCommentsFilter {
 impl TokenListFilter for StripCommentsFilter {
 
     fn process(&mut self, token_list: &mut TokenList) {
-        for token in token_list.tokens.iter_mut() {
-            if token.is_group() && token.typ != TokenType::Comment {
-                self.process(&mut token.children); 
-                token.update_value();
-            }
-        }
+        token_list.visit_mut(self);
         self.process_internal(token_list);
     }
 }
 
+impl VisitorMut for StripCommentsFilter {
+
+    fn pre_visit(&mut self, token: &mut Token<'_>, _ctx: &VisitContext) -> Visit {
+        if token.typ == TokenType::Comment { Visit::SkipChildren } else { Visit::Continue }
+    }
+
+    fn post_visit(&mut self, token: &mut Token<'_>, _ctx: &VisitContext) -> Visit {
+        if token.is_group() && token.typ != TokenType::Comment {
+            self.process_internal(&mut token.children);
+        }
+        Visit::Continue
+    }
+}
+
 pub struct StripWhitespaceFilter { }
 
 impl StripWhitespaceFilter {
@@ -85,7 +95,7 @@ impl StripWhitespaceFilter {
         let n = tokens.len();
         for (i, token) in tokens.iter_mut().enumerate() {
             if token.is_whitespace() {
-                token.value = if last_was_ws || is_first_char || i+1 == n { "".to_string() } else { " ".to_string() };
+                token.set_value(if last_was_ws || is_first_char || i+1 == n { "" } else { " " });
             }
             last_was_ws = token.is_whitespace();
             is_first_char = false;
@@ -114,7 +124,7 @@ impl StripWhitespaceFilter {
     }
 
     fn stripws_parenthesis(token: &mut Token) {
-        if token.typ != TokenType::Parenthesis {
+        if token.typ != TokenType::Parenthesis && token.typ != TokenType::Subquery {
             return
         }
         if token.children.token_idx(Some(1)).map(|t| t.is_whitespace()).unwrap_or(false) {
@@ -174,14 +184,17 @@ impl TokenListFilter for SpacesAroundOperatorsFilter {
 
     fn process(&mut self, token_list: &mut TokenList) {
         self.process_internal(token_list);
-        for token in token_list.tokens.iter_mut() {
-            if token.is_group() {
-                // let before = token.children.len();
-                self.process(&mut token.children);
-                // println!("before {}, after {}", before, token.children.len());
-                token.update_value();
-            }
+        token_list.visit_mut(self);
+    }
+}
+
+impl VisitorMut for SpacesAroundOperatorsFilter {
+
+    fn pre_visit(&mut self, token: &mut Token<'_>, _ctx: &VisitContext) -> Visit {
+        if token.is_group() {
+            self.process_internal(&mut token.children);
         }
+        Visit::Continue
     }
 }
 
@@ -201,8 +214,9 @@ impl StripBeforeNewline {
         for (i, token) in tokens.iter_mut().enumerate() {      
             if token.is_group() {
                 self.process_internal(&mut token.children.tokens, level+1);
+                token.update_value();
             }
-            if is_before_white && (token.value.starts_with("\n") || token.value.starts_with("\r")) && i > 0 {
+            if is_before_white && (token.value().starts_with("\n") || token.value().starts_with("\r")) && i > 0 {
                 remove_indexes.push(i-1);
             }
             is_before_white = if token.is_group() {
@@ -244,12 +258,23 @@ mod tests {
         let token = Token::new(TokenType::CommentSingle, "-- comment \n\n ");
         let t = filter.get_insert_token(&token);
         assert_eq!(t.typ, TokenType::Newline);
-        assert_eq!(t.value, "\n\n");
+        assert_eq!(t.value(), "\n\n");
 
         let token = Token::new(TokenType::CommentSingle, "-- comment ");
         let t = filter.get_insert_token(&token);
         assert_eq!(t.typ, TokenType::Whitespace);
-        assert_eq!(t.value, " ");
+        assert_eq!(t.value(), " ");
 
     }
+
+    #[test]
+    fn test_strip_before_newline_group() {
+        let children = ["(", "a", " ", "\n", ")"].iter()
+            .map(|v| Token::new(if *v == " " { TokenType::Whitespace } else if *v == "\n" { TokenType::Newline } else { TokenType::Punctuation }, *v))
+            .collect();
+        let mut tokens = vec![Token::new_parent(TokenType::Parenthesis, children)];
+        assert_eq!(tokens[0].value(), "(a \n)");
+        StripBeforeNewline{}.process(&mut tokens);
+        assert_eq!(tokens[0].value(), "(a\n)");
+    }
 }
\ No newline at end of file
@@ -58,7 +58,7 @@ impl ReindentFilter {
         line.len().saturating_sub(self.chr.len()*self.leading_ws())
     }
 
-    fn nl(&self, offset: isize) -> Token {
+    fn nl(&self, offset: isize) -> Token<'static> {
         let i = 0.max(self.leading_ws() as isize +offset) as usize;
         let white = format!("{}{}", self.n, self.chr.repeat(i));
         Token::new(TokenType::Whitespace, white)
@@ -83,7 +83,9 @@ impl ReindentFilter {
         while let Some(mut idx) = tidx {
             let pidx = token_list.token_prev(idx, false);
             let prev = token_list.token_idx(pidx);
-            let is_newline = prev.map(|t| t.value.ends_with("\n") || t.value.ends_with("\r")).unwrap_or(false);
+            // a clause group starts with its keyword, the text before the group is in prev_sql
+            let prev_value = prev.map(|t| t.value()).unwrap_or(if idx == 0 { &self.prev_sql } else { "" });
+            let is_newline = prev_value.ends_with("\n") || prev_value.ends_with("\r");
             if prev.map(|t| t.is_whitespace()).unwrap_or(false) {
                 token_list.tokens.remove(pidx.unwrap());
                 idx -= 1;
@@ -120,11 +122,12 @@ impl ReindentFilter {
     fn process_internal(&mut self, token_list: &mut TokenList, token_type: &TokenType, parents: Vec<TokenType>) {
         match token_type {
             TokenType::Where => self.process_where(token_list, parents),
-            TokenType::Parenthesis => self.process_parenthesis(token_list, parents),
+            TokenType::Parenthesis | TokenType::Subquery => self.process_parenthesis(token_list, parents),
             TokenType::Values => self.process_values(token_list),
             TokenType::Case => self.process_case(token_list, parents),
             TokenType::IdentifierList => self.process_identifierlist(token_list, parents),
             TokenType::Function => self.process_function(token_list, parents),
+            TokenType::WindowSpec => self.process_window_spec(token_list),
             _ => self.process_default(token_list, true, parents),
         }
     }
@@ -154,7 +157,7 @@ impl ReindentFilter {
         self.indent += indent;
         let offset = if tidx.is_some() {
             let t = self.nl(0);
-            let offset = self.get_offset(&t.value);
+            let offset = self.get_offset(t.value());
             token_list.insert_before(0, t);
             offset+1
         } else { self.get_offset("")+1 };
@@ -166,12 +169,17 @@ impl ReindentFilter {
     }
 
     fn process_function(&mut self, token_list: &mut TokenList, mut parents: Vec<TokenType>) {
-        self.last_func_len = token_list.tokens[0].value.len();
+        self.last_func_len = token_list.tokens[0].value().len();
         parents.push(TokenType::Function);
         self.process_default(token_list, true, parents);
         self.last_func_len = 0;
     }
 
+    // window specifications stay on one line
+    fn process_window_spec(&mut self, token_list: &mut TokenList) {
+        token_list.tokens.iter().for_each(|t| self.prev_sql.push_str(t.value()));
+    }
+
     fn process_identifierlist(&mut self, token_list: &mut TokenList, mut parents: Vec<TokenType>) {
         // println!("{}", token_list);
         let mut identifiers = token_list.get_identifiers();
@@ -181,7 +189,7 @@ impl ReindentFilter {
         } else {
             if self.chr == "\t" { 1 } else {
                 let first = identifiers.remove(0);
-                let extra = token_list.tokens.iter().take(first).map(|t| t.value.as_str()).collect::<Vec<&str>>().join("");
+                let extra = token_list.tokens.iter().take(first).map(|t| t.value()).collect::<Vec<&str>>().join("");
                 self.get_offset(&extra)
             }
         };
@@ -193,7 +201,7 @@ impl ReindentFilter {
                 tidx += insert_count;
                 let token = token_list.token_idx(Some(tidx)).unwrap();
                 // Add 1 for the "," separator
-                position += token.value.len() + 1;
+                position += token.value().len() + 1;
                 if position + self.offset > self.wrap_after {
                     let mut adjust: isize = 0;
                     if self.comma_first {
@@ -223,7 +231,7 @@ impl ReindentFilter {
             let mut n = 0;
             while n < token_list.len() {
                 let token = token_list.token_idx(Some(n)).unwrap();
-                if token.value != "," {
+                if token.value() != "," {
                     n += 1;
                     continue
                 }
@@ -236,7 +244,7 @@ impl ReindentFilter {
                 n += 1;
             }
 
-            let end_at: usize = identifiers.iter().map(|i| token_list.tokens[*i].value.len()+1).sum();
+            let end_at: usize = identifiers.iter().map(|i| token_list.tokens[*i].value().len()+1).sum();
             let mut adjusted_offset: isize = 0;
             if self.wrap_after > 0 && end_at + self.offset > self.wrap_after && self.last_func_len > 0{
                 adjusted_offset = 0-(self.last_func_len as isize)-1;
@@ -255,7 +263,7 @@ impl ReindentFilter {
                 for mut tidx in identifiers {
                     tidx += insert_count;
                     let token = token_list.token_idx(Some(tidx)).unwrap();
-                    position += token.value.len() + 1;
+                    position += token.value().len() + 1;
                     if self.wrap_after > 0 && position + self.offset > self.wrap_after {
                         if !token_list.insert_newline_before(tidx, self.nl(0)) {
                             insert_count += 1;
@@ -282,7 +290,7 @@ impl ReindentFilter {
             let offset = self.get_offset("");
             self.offset += offset;
             {
-                let extra = token_list.tokens.iter().take(first).map(|t| t.value.as_str()).collect::<Vec<&str>>().join("");
+                let extra = token_list.tokens.iter().take(first).map(|t| t.value()).collect::<Vec<&str>>().join("");
                 let offset = self.get_offset(&extra);
                 self.offset += offset;
                 let mut insert_count = 0; // insert newline count
@@ -347,7 +355,7 @@ impl ReindentFilter {
                 //     remove_indexes.push(i-1);
                 // }
             } else {
-                self.prev_sql.push_str(&token.value);
+                self.prev_sql.push_str(token.value());
             }
         }
         // remove_indexes.iter().enumerate().for_each(|(i, idx)| {token_list.tokens.remove(idx-i);});
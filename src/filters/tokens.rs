@@ -34,8 +34,8 @@ impl Filter for KeywordCaseFilter {
     fn process(&self, token: &mut Token) {
         if token.is_keyword() {
              match self.case {
-                Case::Upper => { token.value = token.value.to_uppercase() },
-                Case::Lower => { token.value = token.value.to_lowercase() },
+                Case::Upper => { token.set_value(token.value().to_uppercase()) },
+                Case::Lower => { token.set_value(token.value().to_lowercase()) },
                 _ => {},
             };
         }
@@ -58,10 +58,10 @@ impl Filter for IdentifierCaseFilter {
 
     fn process(&self, token: &mut Token) { 
         if token.typ == TokenType::Name || token.typ == TokenType::StringSymbol {
-            if !token.value.starts_with("\"") {
+            if !token.value().starts_with("\"") {
                 match self.case {
-                    Case::Upper => { token.value = token.value.to_uppercase() },
-                    Case::Lower => { token.value = token.value.to_lowercase() },
+                    Case::Upper => { token.set_value(token.value().to_uppercase()) },
+                    Case::Lower => { token.set_value(token.value().to_lowercase()) },
                     _ => {},
                 };
             }
@@ -79,6 +79,6 @@ mod tests {
         let f = KeywordCaseFilter::new("upper");
         let mut t = Token::new(TokenType::Keyword, "select");
         f.process(&mut t);
-        assert_eq!(t.value, "SELECT");
+        assert_eq!(t.value(), "SELECT");
     }
 }
\ No newline at end of file
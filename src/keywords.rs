@@ -1,6 +1,8 @@
 // use std::collections::HashMap;
 use regex::{Regex};
+use regex_syntax::hir::{Hir, HirKind, Class};
 use super::{TokenType, trie::TokenTypeTrie};
+use super::dialect::{Dialect, GenericDialect, Placeholder};
 // use crate::sql::token::regex_factory::{ create_string_regex };
 
 pub struct RegexToken {
@@ -10,13 +12,14 @@ pub struct RegexToken {
     pub backward: usize, // backward offset, match .Name
     pub needle: Option<&'static str>,
     pub shortest: bool,
+    pub first_bytes: [bool; 256], // bytes a match can start with
 }
 
 impl RegexToken {
     
     fn new(s: &str, typ: TokenType, capture: Option<usize>, backward: usize, shortest: bool) -> Self {
         // let reg = if ignore_case { RegexBuilder::new(s).case_insensitive(true).build().unwrap() } else { Regex::new(s).unwrap() };
-        let reg = Regex::new(s).unwrap();
+        let reg = anchored(s);
         Self{
             reg, 
             typ,
@@ -24,12 +27,13 @@ impl RegexToken {
             backward,
             needle: None,
             shortest: shortest,
+            first_bytes: first_bytes(s),
         }
     }
 
     fn new_nd(s: &str, typ: TokenType, capture: Option<usize>, backward: usize, needle: &'static str, shortest: bool) -> Self {
         // let reg = if ignore_case { RegexBuilder::new(s).case_insensitive(true).build().unwrap() } else { Regex::new(s).unwrap() };
-        let reg = Regex::new(s).unwrap();
+        let reg = anchored(s);
         Self{
             reg, 
             typ,
@@ -37,11 +41,13 @@ impl RegexToken {
             backward,
             needle: Some(needle),
             shortest: shortest,
+            first_bytes: first_bytes(s),
         }
     }
 
     fn _new_reg(r: Regex, typ: TokenType) -> Self {
         Self{
+            first_bytes: first_bytes(r.as_str()),
             reg: r, 
             typ,
             capture: None,
@@ -52,6 +58,63 @@ impl RegexToken {
     }
 }
 
+// tokens start at the current position, an anchored regex fails without scanning the rest of the input
+fn anchored(s: &str) -> Regex {
+    Regex::new(&format!("^(?:{})", s)).unwrap()
+}
+
+// the lexer skips a rule when the current byte is not in its first bytes
+fn first_bytes(s: &str) -> [bool; 256] {
+    let mut bytes = [false; 256];
+    let hir = regex_syntax::parse(s).unwrap();
+    if hir_first_bytes(&hir, &mut bytes) {
+        bytes = [true; 256];
+    }
+    bytes
+}
+
+// marks the first bytes of hir, returns true if hir matches the empty string
+fn hir_first_bytes(hir: &Hir, bytes: &mut [bool; 256]) -> bool {
+    match hir.kind() {
+        HirKind::Empty | HirKind::Look(_) => true,
+        HirKind::Literal(lit) => {
+            match lit.0.first() {
+                Some(b) => { bytes[*b as usize] = true; false },
+                None => true,
+            }
+        },
+        HirKind::Class(Class::Unicode(cls)) => {
+            for r in cls.ranges() {
+                let (start, end) = (r.start() as u32, r.end() as u32);
+                for b in start.min(0x80)..=end.min(0x7F) {
+                    bytes[b as usize] = true;
+                }
+                // leading bytes of multibyte utf-8 characters
+                if end >= 0x80 {
+                    bytes[0xC0..].iter_mut().for_each(|b| *b = true);
+                }
+            }
+            cls.ranges().is_empty()
+        },
+        HirKind::Class(Class::Bytes(cls)) => {
+            for r in cls.ranges() {
+                bytes[r.start() as usize..=r.end() as usize].iter_mut().for_each(|b| *b = true);
+            }
+            cls.ranges().is_empty()
+        },
+        HirKind::Repetition(rep) => hir_first_bytes(&rep.sub, bytes) || rep.min == 0,
+        HirKind::Capture(cap) => hir_first_bytes(&cap.sub, bytes),
+        HirKind::Concat(subs) => subs.iter().all(|sub| hir_first_bytes(sub, bytes)),
+        HirKind::Alternation(subs) => {
+            let mut empty = false;
+            for sub in subs {
+                empty |= hir_first_bytes(sub, bytes);
+            }
+            empty
+        },
+    }
+}
+
 #[inline]
 fn new_rt(s: &str, typ: TokenType) -> RegexToken{
     RegexToken::new(s, typ, None, 0, false)
@@ -80,13 +143,21 @@ fn new_cap(s: &str, typ: TokenType, i: usize, probe: &'static str) -> RegexToken
 
 // TODO: start with ^
 pub fn sql_regex() -> Vec<RegexToken> {
+    sql_regex_dialect(&GenericDialect)
+}
+
+pub fn sql_regex_dialect(dialect: &dyn Dialect) -> Vec<RegexToken> {
+    // unicode identifiers, https://www.unicode.org/reports/tr31/
+    let extra = regex::escape(&dialect.identifier_chars().iter().collect::<String>());
+    let ident = format!(r"[\p{{XID_Start}}_][\p{{XID_Continue}}{}]*", extra);
     // let literal = create_string_regex(vec!["''", r#""""#, "``"]).unwrap();
-    vec![
+    let mut regs = vec![
         new_rt(r"(--|# )\+.*?(\r\n|\r|\n|$)", TokenType::CommentSingleHint),
-        new_rt(r"/\*\+[\s\S]*?\*/", TokenType::CommentMultilineHint),
+        // a comment without a close after it is not scanned to the end of the input again
+        new_nd(r"/\*\+[\s\S]*?\*/", TokenType::CommentMultilineHint, "*/"),
 
         new_rt(r"(--|# ).*?(\r\n|\r|\n|$)", TokenType::CommentSingle),
-        new_rt(r"/\*[\s\S]*?\*/", TokenType::CommentMultiline),
+        new_nd(r"/\*[\s\S]*?\*/", TokenType::CommentMultiline, "*/"),
 
         new_rt(r"(\r\n|\r|\n)", TokenType::Newline),
         new_rt(r"\s+?", TokenType::Whitespace),
@@ -95,36 +166,78 @@ pub fn sql_regex() -> Vec<RegexToken> {
         new_rt(r"::", TokenType::Punctuation),
 
         new_rt(r"\*", TokenType::Wildcard),
+    ];
+    if dialect.dollar_quoted_strings() {
+        // opening tag only, the lexer searches the closing tag
+        regs.push(new_rt(r"^\$([A-Za-z_]\w*)?\$", TokenType::StringDollar));
+    }
+    for quote in dialect.identifier_quotes().iter().filter(|q| **q != '"') {
+        let open = regex::escape(&quote.to_string());
+        let close = if *quote == '[' { regex::escape("]") } else { open.clone() };
+        regs.push(new_rt(&format!("{open}({close}{close}|[^{close}])*{close}"), TokenType::Name));
+    }
+    // RegexToken::new_reg(literal, TokenType::Literal),
 
-        new_rt(r"`(``|[^`])*`", TokenType::Name),
-        new_rt(r"´(´´|[^´])*´", TokenType::Name),
-        // RegexToken::new_reg(literal, TokenType::Literal),
+    let placeholders = dialect.placeholders();
+    if placeholders.contains(&Placeholder::QuestionMark) {
+        regs.push(new_rt(r"\?", TokenType::NamePlaceholder));
+    }
+    if placeholders.contains(&Placeholder::Format) {
+        regs.push(new_rt(r"%(\(\w+\))?s", TokenType::NamePlaceholder));
+    }
+    let prefixes = [(Placeholder::Dollar, "$"), (Placeholder::Colon, ":"), (Placeholder::QuestionMark, "?"), (Placeholder::At, "@")].iter()
+        .filter(|(p, _)| placeholders.contains(p))
+        .map(|(_, prefix)| *prefix)
+        .collect::<String>();
+    if !prefixes.is_empty() {
+        regs.push(new_rt(&format!(r"^[{}]\w+", prefixes), TokenType::NamePlaceholder));
+    }
+    if dialect.at_variables() {
+        regs.push(new_rt(r"^@@?[\w$#]+", TokenType::Name));
+    }
 
-        new_rt(r"\?", TokenType::NamePlaceholder),
-        new_rt(r"%(\(\w+\))?s", TokenType::NamePlaceholder),
-        new_rt(r"^[$:?]\w+", TokenType::NamePlaceholder),
+    // prefixed and triple quoted strings keep their prefix in a single token
+    if dialect.raw_strings() {
+        regs.push(new_rt(r"(?i)^(R|B|RB|BR)?'''[\s\S]*?'''", TokenType::StringSingle));
+        regs.push(new_rt(r#"(?i)^(R|B|RB|BR)?"""[\s\S]*?""""#, TokenType::String));
+        regs.push(new_rt(r"(?i)^(R|B|RB|BR)'(\\[\s\S]|[^'\\])*'", TokenType::StringSingle));
+        regs.push(new_rt(r#"(?i)^(R|B|RB|BR)"(\\[\s\S]|[^"\\])*""#, TokenType::String));
+    }
+    if dialect.escape_strings() {
+        regs.push(new_rt(r"(?i)^E'(''|\\[\s\S]|[^'\\])*'", TokenType::StringSingle));
+    }
+    regs.push(new_rt(r"(?i)^(N|X|B|U&)'(''|[^'])*'", TokenType::StringSingle));
 
+    regs.extend(vec![
         new_rt(r"\\\w+", TokenType::Command),
         new_rt(r"(?i)(NOT\s+)?IN\b", TokenType::OperatorComparison),
 
         new_shr(r"(?i)^(CASE|IN|VALUES|USING|FROM|AS)\b", TokenType::Keyword),
 
-        new_rt(r"(?i)(@|##|#)[A-ZÀ-Ü]\w+", TokenType::Name), // max name length is 64
-        new_cap(r"(?i)([A-ZÀ-Ü]\w*)(?:\s*\.)", TokenType::Name, 1, "."),
+        new_rt(&format!(r"(@|##|#)[\p{{XID_Start}}_][\p{{XID_Continue}}{}]+", extra), TokenType::Name), // max name length is 64
+        new_cap(&format!(r"({})(?:\s*\.)", ident), TokenType::Name, 1, "."),
         // FIXME: backword match  .name
-        RegexToken::new(r"(?i:\.)(?i)([A-ZÀ-Ü]\w*)", TokenType::Name, Some(1), 1, false),
-        new_cap(r"(?i)([A-ZÀ-Ü]\w*)(?:\()", TokenType::Name, 1, "("),
+        RegexToken::new(&format!(r"(?:\.)({})", ident), TokenType::Name, Some(1), 1, false),
+        new_cap(&format!(r"({})(?:\()", ident), TokenType::Name, 1, "("),
 
         new_rt(r"-?0x[\dA-Fa-f]+", TokenType::NumberHexadecimal),
         new_rt(r"-?\d+(\.\d+)?[Ee]-?\d+", TokenType::NumberFloat),
         new_nd(r"-?(\d+(\.\d*)|\.\d+)", TokenType::NumberFloat, "."),
         new_rt(r"(-\s*)?[0-9]+", TokenType::NumberInteger),
+    ]);
+
+    let double_quote = if dialect.identifier_quotes().contains(&'"') { TokenType::StringSymbol } else { TokenType::String };
+    if dialect.backslash_escapes() {
+        regs.push(new_rt(r"'(''|\\\\|\\'|[^'])*'", TokenType::StringSingle));
+        regs.push(new_rt(r#""(""|\\\\|\\"|[^"])*""#, double_quote.clone()));
+        regs.push(new_rt(r#"(""|".*?[^\\]")"#, double_quote));
+    } else {
+        regs.push(new_rt(r"'(''|[^'])*'", TokenType::StringSingle));
+        regs.push(new_rt(r#""(""|[^"])*""#, double_quote));
+    }
+    // new_rt(r#"(?:[^\w\])])(\[[^\]\[]+\])"#, TokenType::Name),
 
-        new_rt(r"'(''|\\\\|\\'|[^'])*'", TokenType::StringSingle),
-        new_rt(r#""(""|\\\\|\\"|[^"])*""#, TokenType::StringSymbol),
-        new_rt(r#"(""|".*?[^\\]")"#, TokenType::StringSymbol),
-        // new_rt(r#"(?:[^\w\])])(\[[^\]\[]+\])"#, TokenType::Name),
-
+    regs.extend(vec![
         new_nd(r"(?i)((LEFT\s+|RIGHT\s+|FULL\s+)?(INNER\s+|OUTER\s+|STRAIGHT\s+)?|(CROSS\s+|NATURAL\s+)?)?JOIN\b", TokenType::Keyword, "join"),
         new_rt(r"(?i)END(\s+IF|\s+LOOP|\s+WHILE)?\b", TokenType::Keyword),
         new_rt(r"(?i)NOT\s+NULL\b", TokenType::Keyword),
@@ -138,11 +251,18 @@ pub fn sql_regex() -> Vec<RegexToken> {
         new_rt(r"(?i)(LATERAL\s+VIEW\s+)(EXPLODE|INLINE|PARSE_URL_TUPLE|POSEXPLODE|STACK)\b", TokenType::Keyword),
         new_rt(r"(?i)(AT|WITH')\s+TIME\s+ZONE\s+'[^']+'", TokenType::KeywordTZCast),
         new_rt(r"(?i)(NOT\s+)?(LIKE|ILIKE|RLIKE)\b", TokenType::OperatorComparison),
-        new_rt(r"(?i)[0-9_A-Z][_$#\w]{0,26}", TokenType::KeywordRaw), // min length keyword: as, max length keyword: TRANSACTIONS_ROLLED_BACK TODO: move to special case match with trie
+        new_rt(&format!(r"[0-9_\p{{XID_Start}}][\p{{XID_Continue}}{}]{{0,26}}", extra), TokenType::KeywordRaw), // min length keyword: as, max length keyword: TRANSACTIONS_ROLLED_BACK TODO: move to special case match with trie
         new_rt(r"[;:()\[\],\.]", TokenType::Punctuation),
         new_rt(r"[<>=~!]+", TokenType::OperatorComparison),
         new_rt(r"[+/@#%^&|^-]+", TokenType::Operator)
-    ]
+    ]);
+    regs
+}
+
+// keyword type of a word in the dialect
+pub fn dialect_keyword(dialect: &dyn Dialect, k: &str) -> TokenType {
+    let keyword = k.to_uppercase();
+    dialect.keyword(&keyword).unwrap_or_else(|| is_keyword(&keyword))
 }
 
 // TODO: hash map
@@ -208,6 +328,13 @@ pub fn is_keyword(k: &str) -> TokenType {
     }
 }
 
+// reserved only by one database, plain names in the other dialects
+pub(crate) const KEYWORDS_POSTGRES: [&str; 26] = ["PLPGSQL", "NOTICE", "PERFORM", "INHERIT", "INDEXES", "ON_ERROR_STOP", "BYTEA", "BIGSERIAL", "SMALLSERIAL", "SERIAL8",
+"BOX", "CIDR", "CIRCLE", "INET", "JSONB", "LINE", "LSEG", "MACADDR", "PATH", "PG_LSN", "POINT", "POLYGON", "TSQUERY", "TSVECTOR", "TXID_SNAPSHOT", "MONEY"];
+pub(crate) const KEYWORDS_MYSQL: [&str; 4] = ["AUTO_INCREMENT", "ENGINE", "CHARSET", "UNSIGNED"];
+pub(crate) const KEYWORDS_ORACLE: [&str; 18] = ["VARCHAR2", "PLS_INTEGER", "NATURALN", "POSITIVE", "POSITIVEN", "SIGNTYPE", "SIMPLE_DOUBLE", "SIMPLE_FLOAT", "SIMPLE_INTEGER",
+"ROWLABEL", "UROWID", "ROWNUM", "SYSDATE", "SYS_REFCURSOR", "UTL_FILE", "FILE_TYPE", "MAXEXTENTS", "PCTFREE"];
+
 const KEYWORDS_DML: [&str; 13] = ["SELECT" , "INSERT" , "DELETE" , "UPDATE" , "UPSERT" , "REPLACE" ,  "MERGE" , "DROP" , "CREATE" , "ALTER", "COMMIT", "ROLLBACK", "START"];
 const KEYWORDS_ORDER: [&str; 2] = ["ASC" , "DESC"];
 const NAME_BUILTIN: [&str; 46] = ["ARRAY" , "BIGINT" , "BINARY" , "BIT" , "BLOB" , "BOOLEAN" , "CHAR" , "DATE" , "DEC" , "DECIMAL" , "FILE_TYPE" , "FLOAT" , "INT" , "INT8" , "INTEGER" , "INTERVAL" , "LONG" , "NATURALN" , "NVARCHAR" , "NUMBER" , "NUMERIC" , "PLS_INTEGER" , "POSITIVE" , "POSITIVEN" , "REAL" , "ROWID" , "ROWLABEL",
@@ -286,6 +413,19 @@ mod tests {
         assert!(regs.len() > 0)
     }
 
+    #[test]
+    fn test_first_bytes() {
+        let bytes = first_bytes(r"(?i)(NOT\s+)?IN\b");
+        assert!(bytes[b'n' as usize] && bytes[b'N' as usize] && bytes[b'i' as usize] && bytes[b'I' as usize]);
+        assert!(!bytes[b'a' as usize] && !bytes[b' ' as usize]);
+        let bytes = first_bytes(r"[\p{XID_Start}_]\w*");
+        assert!(bytes[b'_' as usize] && bytes["é".as_bytes()[0] as usize]);
+        assert!(!bytes[b'1' as usize]);
+        // a pattern matching the empty string may start anywhere
+        let bytes = first_bytes(r"a?");
+        assert!(bytes.iter().all(|b| *b));
+    }
+
     #[test]
     fn test_non_capturing_group() {
         let reg = Regex::new(r"([A-ZÀ-Ü]\w*)(?:\()").unwrap();
@@ -1,48 +1,147 @@
-use super::keywords::{sql_regex, is_keyword, init_trie, RegexToken};
-use super::tokens::TokenType;
+use std::borrow::Cow;
+use std::sync::OnceLock;
+use super::keywords::{sql_regex, dialect_keyword, init_trie, RegexToken};
+use super::dialect::{Dialect, GenericDialect};
+use super::tokens::{TokenType, JoinKind};
 use super::trie::{TokenTypeTrie};
 
-/// parsed sql token
-#[derive(Debug, Clone, PartialEq)]
-pub struct Token {
+/// position of a token in the source sql
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Span {
+    /// byte offset of the first character
+    pub start: usize,
+    /// byte offset after the last character
+    pub end: usize,
+    /// line of the first character, starts from 1, 0 if the token is not from the source
+    pub line: usize,
+    /// column of the first character counted in chars, starts from 1
+    pub column: usize,
+}
+
+impl Span {
+
+    pub fn new(start: usize, end: usize, line: usize, column: usize) -> Self {
+        Self { start, end, line, column }
+    }
+
+    /// tokens created by filters have no position in the source
+    pub fn is_empty(&self) -> bool {
+        self.line == 0
+    }
+
+    // union span of tokens, tokens without position are skipped
+    pub fn union(tokens: &[Token<'_>]) -> Self {
+        let mut spans = tokens.iter().map(|t| t.span).filter(|s| !s.is_empty());
+        let first = match spans.next() {
+            Some(s) => s,
+            None => return Span::default(),
+        };
+        spans.fold(first, |acc, s| {
+            let (line, column) = if s.start < acc.start { (s.line, s.column) } else { (acc.line, acc.column) };
+            Span::new(acc.start.min(s.start), acc.end.max(s.end), line, column)
+        })
+    }
+}
+
+/// parsed sql token, borrows its text from the source sql
+#[derive(Debug, Clone)]
+pub struct Token<'a> {
     pub typ: TokenType,
-    pub value: String,
-    pub children: TokenList,
-    pub normalized: String,
+    // text of a leaf token, the normalized text of a keyword group
+    text: Cow<'a, str>,
+    // set on first use, the text of a group joined from its children or the uppercased text of a keyword
+    cache: OnceLock<Box<str>>,
+    pub children: TokenList<'a>,
+    pub span: Span,
+}
+
+impl PartialEq for Token<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.typ == other.typ && self.value() == other.value() && self.children == other.children && self.span == other.span
+    }
 }
 
 /// grouped tokens
 #[derive(Debug, Clone, PartialEq)]
-pub struct TokenList {
+pub struct TokenList<'a> {
     // pub parent_type: TokenType,
-    pub tokens: Vec<Token>,
+    pub tokens: Vec<Token<'a>>,
 }
 
 // impl Iterator for TokenList {
 // }
 
-impl Token {
+impl<'a> Token<'a> {
 
-    pub fn new<T: AsRef<str>+ToString>(typ: TokenType, value: T) -> Self {
-        let token_list = TokenList::new(vec![]);
-        let normalized = if Token::is_keyword_internal(&typ) { value.as_ref().to_uppercase() } else { value.to_string() };
-        Self { typ, value: value.to_string(), children: token_list, normalized }
+    pub fn new<T: Into<Cow<'a, str>>>(typ: TokenType, value: T) -> Self {
+        Self { typ, text: value.into(), cache: OnceLock::new(), children: TokenList::new(vec![]), span: Span::default() }
     }
 
-    pub fn new_parent(typ: TokenType, children: Vec<Token>) -> Self {
-        let value = Token::new_value(&children);
-        let token_list = TokenList::new(children);
-        let normalized = if Token::is_keyword_internal(&typ) { value.to_uppercase().split_whitespace().collect::<Vec<_>>().join(" ") } else { "".to_string() };
-        Self { typ, value, children: token_list, normalized }
+    pub fn new_parent(typ: TokenType, children: Vec<Token<'a>>) -> Self {
+        let span = Span::union(&children);
+        let mut token = Self { typ, text: Cow::Borrowed(""), cache: OnceLock::new(), children: TokenList::new(children), span };
+        token.update_value();
+        token
     }
 
+    /// joined text of tokens, groups are walked without caching their text
     pub fn new_value(children: &[Token]) -> String {
-        children.iter().map(|child| child.value.as_ref()).collect::<Vec<_>>().join("")
+        let mut value = String::new();
+        children.iter().for_each(|child| child.push_value(&mut value));
+        value
     }
 
-    pub fn update_value(&mut self) {
+    fn push_value(&self, value: &mut String) {
+        match self.cache.get() {
+            Some(text) if self.is_group() => value.push_str(text),
+            _ if self.is_group() => self.children.tokens.iter().for_each(|child| child.push_value(value)),
+            _ => value.push_str(&self.text),
+        }
+    }
+
+    /// text of the token, the text of a group is joined from its children
+    pub fn value(&self) -> &str {
         if self.children.len() > 0 {
-            self.value = Token::new_value(&self.children.tokens);
+            self.cache.get_or_init(|| Token::new_value(&self.children.tokens).into())
+        } else {
+            &self.text
+        }
+    }
+
+    pub fn set_value<T: Into<Cow<'a, str>>>(&mut self, value: T) {
+        self.text = value.into();
+        self.cache.take();
+    }
+
+    /// uppercased keyword, the text of other leaf tokens
+    pub fn normalized(&self) -> &str {
+        if self.is_group() {
+            return if self.is_keyword() { &self.text } else { "" }
+        }
+        if self.is_keyword() && self.text.chars().any(|c| c.is_lowercase()) {
+            self.cache.get_or_init(|| self.text.to_uppercase().into())
+        } else {
+            &self.text
+        }
+    }
+
+    /// token owning its text, it outlives the source sql
+    pub fn into_owned(self) -> Token<'static> {
+        Token {
+            typ: self.typ,
+            text: Cow::Owned(self.text.into_owned()),
+            cache: self.cache,
+            children: TokenList::new(self.children.tokens.into_iter().map(Token::into_owned).collect()),
+            span: self.span,
+        }
+    }
+
+    // must be called after the children are changed
+    pub fn update_value(&mut self) {
+        self.cache.take();
+        if self.is_group() && self.is_keyword() {
+            let value = Token::new_value(&self.children.tokens);
+            self.text = Cow::Owned(value.to_uppercase().split_whitespace().collect::<Vec<_>>().join(" "));
         }
     }
 
@@ -68,7 +167,7 @@ impl Token {
     }
 
     // comparisons token
-    pub fn imt(token: Option<&Token>, types: &[TokenType], pattern: Option<&(TokenType, Vec<&str>)>) -> bool {
+    pub fn imt(token: Option<&Token<'_>>, types: &[TokenType], pattern: Option<&(TokenType, Vec<&str>)>) -> bool {
         if token.is_none() {
             return false
         }
@@ -76,18 +175,18 @@ impl Token {
         if types.len() > 0 {
             return types.iter().find(|typ| **typ == token.typ).is_some()
         } else if let Some(p) = pattern {
-            return p.0 == token.typ && p.1.iter().find(|v| **v == token.normalized).is_some()
+            return p.0 == token.typ && p.1.contains(&token.normalized())
         } else {
             return false
         }
     }
 
-    pub fn get_name(&self) -> Option<&str> {
+    pub fn get_name(&self) -> Option<Cow<'_, str>> {
         self.get_alias().or(self.get_real_name())
     }
 
     // Returns the real name (object name) of this identifier.
-    pub fn get_real_name(&self) -> Option<&str> {
+    pub fn get_real_name(&self) -> Option<Cow<'_, str>> {
         if !self.is_group() {
             return None
         }
@@ -97,16 +196,16 @@ impl Token {
         children.get_first_name(dot_idx, false, false, true)
     }
 
-    pub fn get_parent_name(&self) -> Option<&str> {
+    pub fn get_parent_name(&self) -> Option<Cow<'_, str>> {
         let pattern = (TokenType::Punctuation, vec!["."]);
         let children = &self.children;
         let dot_idx = children.token_next_by(&vec![], Some(&pattern), 0);
         let prev_idx = dot_idx.map(|idx| children.token_prev(idx, true)).flatten();
         let prev = children.token_idx(prev_idx);
-        prev.map(|p| remove_quotes(&p.value))
+        prev.map(|p| unquote_name(p.value()))
     }
 
-    pub fn get_alias(&self) -> Option<&str> {
+    pub fn get_alias(&self) -> Option<Cow<'_, str>> {
         if self.typ != TokenType::Identifier {
             return None
         }
@@ -125,6 +224,195 @@ impl Token {
         None
     }
 
+    /// common table expressions of a WITH group
+    pub fn get_ctes(&self) -> Vec<&Token<'a>> {
+        if self.typ != TokenType::With {
+            return vec![]
+        }
+        self.children.tokens.iter()
+            .flat_map(|t| if t.typ == TokenType::IdentifierList { t.children.tokens.iter().collect() } else { vec![t] })
+            .filter(|t| t.typ == TokenType::CommonTableExpression)
+            .collect()
+    }
+
+    /// WITH RECURSIVE
+    pub fn is_recursive(&self) -> bool {
+        self.typ == TokenType::With && self.children.tokens.iter().any(|t| t.is_keyword() && t.normalized() == "RECURSIVE")
+    }
+
+    /// column names of a common table expression, `name (a, b) AS (...)`
+    pub fn get_cte_columns(&self) -> Vec<&str> {
+        let head = match self.children.tokens.first() {
+            Some(head) if self.typ == TokenType::CommonTableExpression && head.typ == TokenType::Function => head,
+            _ => return vec![],
+        };
+        let columns = head.children.tokens.iter().find(|t| t.typ == TokenType::Parenthesis);
+        columns.map(|p| p.children.tokens.iter()
+            .flat_map(|t| if t.typ == TokenType::IdentifierList { t.children.tokens.iter().collect() } else { vec![t] })
+            .filter(|t| matches!(t.typ, TokenType::Identifier | TokenType::Name | TokenType::StringSymbol))
+            .map(|t| remove_quotes(t.value()))
+            .collect()).unwrap_or_default()
+    }
+
+    /// query of a common table expression
+    pub fn get_cte_body(&self) -> Option<&Token<'a>> {
+        if self.typ != TokenType::CommonTableExpression {
+            return None
+        }
+        self.children.tokens.iter().rev().find(|t| matches!(t.typ, TokenType::Subquery | TokenType::Parenthesis))
+    }
+
+    /// kind of a JOIN clause from its keyword
+    pub fn join_kind(&self) -> Option<JoinKind> {
+        if self.typ != TokenType::Join {
+            return None
+        }
+        let keyword = self.children.tokens.first()?.normalized();
+        let kind = match keyword.split_whitespace().next()? {
+            "LEFT" => JoinKind::Left,
+            "RIGHT" => JoinKind::Right,
+            "FULL" => JoinKind::Full,
+            "CROSS" => JoinKind::Cross,
+            "NATURAL" => JoinKind::Natural,
+            "STRAIGHT" | "STRAIGHT_JOIN" => JoinKind::Straight,
+            _ => JoinKind::Inner,
+        };
+        Some(kind)
+    }
+
+    /// joined table or subquery of a JOIN clause
+    pub fn join_target(&self) -> Option<&Token<'a>> {
+        if self.typ != TokenType::Join {
+            return None
+        }
+        self.children.tokens.iter().skip(1)
+            .filter(|t| !t.is_whitespace() && !matches!(t.typ, TokenType::Comment | TokenType::CommentSingle | TokenType::CommentMultiline))
+            .find(|t| !(t.typ == TokenType::Keyword && t.normalized() == "LATERAL"))
+            .filter(|t| !(t.typ == TokenType::Keyword && matches!(t.normalized(), "ON" | "USING")))
+    }
+
+    /// tokens of the ON condition of a JOIN clause
+    pub fn join_condition(&self) -> Option<&[Token<'a>]> {
+        if self.typ != TokenType::Join {
+            return None
+        }
+        let tokens = &self.children.tokens;
+        let on = tokens.iter().position(|t| t.typ == TokenType::Keyword && t.normalized() == "ON")?;
+        let start = tokens[on+1..].iter().position(|t| !t.is_whitespace()).map(|i| on+1+i)?;
+        Some(&tokens[start..])
+    }
+
+    /// column names of the USING list of a JOIN clause
+    pub fn join_using(&self) -> Vec<&str> {
+        if self.typ != TokenType::Join {
+            return vec![]
+        }
+        let tokens = &self.children.tokens;
+        let columns = tokens.iter().position(|t| t.typ == TokenType::Keyword && t.normalized() == "USING")
+            .and_then(|idx| tokens[idx+1..].iter().find(|t| !t.is_whitespace()))
+            .filter(|t| t.typ == TokenType::Parenthesis);
+        columns.map(|p| p.children.tokens.iter()
+            .flat_map(|t| if t.typ == TokenType::IdentifierList { t.children.tokens.iter().collect() } else { vec![t] })
+            .filter(|t| matches!(t.typ, TokenType::Identifier | TokenType::Name | TokenType::StringSymbol))
+            .map(|t| remove_quotes(t.value()))
+            .collect()).unwrap_or_default()
+    }
+
+    /// `OVER` clause of a window function
+    pub fn get_window(&self) -> Option<&Token<'a>> {
+        if self.typ != TokenType::Function {
+            return None
+        }
+        self.children.tokens.iter().find(|t| t.typ == TokenType::Over)
+    }
+
+    /// window name of `OVER name`
+    pub fn get_window_name(&self) -> Option<&str> {
+        if self.typ != TokenType::Over {
+            return None
+        }
+        self.children.tokens.last()
+            .filter(|t| matches!(t.typ, TokenType::Identifier | TokenType::Name | TokenType::StringSymbol))
+            .map(|t| remove_quotes(t.value()))
+    }
+
+    /// window specification of `OVER (...)`
+    pub fn get_window_spec(&self) -> Option<&Token<'a>> {
+        if self.typ != TokenType::Over {
+            return None
+        }
+        self.children.tokens.last().filter(|t| t.typ == TokenType::WindowSpec)
+    }
+
+    /// named windows of a WINDOW clause
+    pub fn get_named_windows(&self) -> Vec<(Cow<'_, str>, &Token<'a>)> {
+        if self.typ != TokenType::Window {
+            return vec![]
+        }
+        self.children.tokens.iter()
+            .flat_map(|t| if t.typ == TokenType::IdentifierList { t.children.tokens.iter().collect() } else { vec![t] })
+            .filter_map(|t| {
+                let spec = t.children.tokens.last().filter(|s| s.typ == TokenType::WindowSpec)?;
+                t.get_name().map(|name| (name, spec))
+            })
+            .collect()
+    }
+
+    /// `PARTITION BY` expressions of a window specification
+    pub fn get_partition_keys(&self) -> Vec<&Token<'a>> {
+        self.window_keys(|t| t.normalized() == "PARTITION")
+    }
+
+    /// `ORDER BY` expressions of a window specification
+    pub fn get_order_keys(&self) -> Vec<&Token<'a>> {
+        self.window_keys(|t| t.normalized() == "ORDER BY")
+    }
+
+    /// `ROWS`, `RANGE` or `GROUPS` frame of a window specification, without the closing parenthesis
+    pub fn get_window_frame(&self) -> Option<&[Token<'a>]> {
+        if self.typ != TokenType::WindowSpec {
+            return None
+        }
+        let tokens = &self.children.tokens;
+        let start = tokens.iter().position(is_frame_start)?;
+        let end = tokens.iter().rposition(|t| !t.is_whitespace() && t.value() != ")")?;
+        Some(&tokens[start..end+1])
+    }
+
+    // expressions after the keyword up to the next keyword of the window specification
+    fn window_keys<F>(&self, f: F) -> Vec<&Token<'a>> where F: Fn(&Token) -> bool {
+        if self.typ != TokenType::WindowSpec {
+            return vec![]
+        }
+        let tokens = &self.children.tokens;
+        let start = match tokens.iter().position(|t| t.typ == TokenType::Keyword && f(t)) {
+            Some(idx) => idx+1,
+            None => return vec![]
+        };
+        tokens[start..].iter()
+            .skip_while(|t| t.is_whitespace() || t.normalized() == "BY")
+            .take_while(|t| !(t.typ == TokenType::Keyword && (t.normalized() == "ORDER BY" || is_frame_start(t))) && t.value() != ")")
+            .flat_map(|t| if t.typ == TokenType::IdentifierList { t.children.tokens.iter().collect() } else { vec![t] })
+            .filter(|t| !t.is_whitespace() && t.typ != TokenType::Punctuation && !matches!(t.typ, TokenType::Comment | TokenType::CommentSingle | TokenType::CommentMultiline))
+            .collect()
+    }
+
+    /// `Some(true)` for `AS MATERIALIZED`, `Some(false)` for `AS NOT MATERIALIZED`
+    pub fn get_materialized(&self) -> Option<bool> {
+        if self.typ != TokenType::CommonTableExpression {
+            return None
+        }
+        let keywords = self.children.tokens.iter().filter(|t| t.typ == TokenType::Keyword).collect::<Vec<_>>();
+        if !keywords.iter().any(|t| t.normalized() == "MATERIALIZED") {
+            return None
+        }
+        Some(!keywords.iter().any(|t| t.normalized() == "NOT"))
+    }
+
+}
+
+fn is_frame_start(token: &Token) -> bool {
+    token.typ == TokenType::Keyword && matches!(token.normalized(), "ROWS" | "RANGE" | "GROUPS")
 }
 
 pub(crate) fn remove_quotes(mut s: &str) -> &str {
@@ -134,53 +422,121 @@ pub(crate) fn remove_quotes(mut s: &str) -> &str {
     } else if s.starts_with("'") {
         s = s.trim_start_matches("'");
         s.trim_end_matches("'")
+    } else if s.starts_with('`') {
+        s = s.trim_start_matches('`');
+        s.trim_end_matches('`')
+    } else if s.starts_with('[') && s.ends_with(']') {
+        &s[1..s.len()-1]
     } else {
         s
     }
 }
 
-pub fn tokenize(sql: &str) -> Vec<Token> {
+// name without quotes, `]]` in a T-SQL bracket identifier is an escaped `]`
+pub(crate) fn unquote_name(s: &str) -> Cow<'_, str> {
+    let name = remove_quotes(s);
+    if s.starts_with('[') && name.contains("]]") { Cow::Owned(name.replace("]]", "]")) } else { Cow::Borrowed(name) }
+}
+
+pub fn tokenize(sql: &str) -> Vec<Token<'_>> {
     let regs = sql_regex();
     let trie = init_trie();
-    tokenize_internal(sql, &regs, &trie)
+    tokenize_internal(sql, &regs, &trie, &GenericDialect)
+}
+
+/// error returned by the fallible tokenizer when no rule matches
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LexError {
+    /// byte offset of the unrecognized character
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+    pub unexpected: char,
+}
+
+impl std::fmt::Display for LexError {
+
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unexpected character {:?} at line {}, column {} (offset {})", self.unexpected, self.line, self.column, self.offset)
+    }
 }
 
-pub fn tokenize_internal(sql: &str, regs: &[RegexToken], trie: &TokenTypeTrie) -> Vec<Token> {
+impl std::error::Error for LexError {}
+
+/// unrecognized characters become TokenType::Error tokens
+pub fn tokenize_internal<'a>(sql: &'a str, regs: &[RegexToken], trie: &TokenTypeTrie, dialect: &dyn Dialect) -> Vec<Token<'a>> {
+    tokenize_inner(sql, regs, trie, dialect, &Resume::default(), false).unwrap_or_default()
+}
+
+/// lex the rest of a script from a statement boundary, spans count from the start of the script
+pub(crate) fn tokenize_resume<'a>(sql: &'a str, regs: &[RegexToken], trie: &TokenTypeTrie, dialect: &dyn Dialect, resume: &Resume) -> Vec<Token<'a>> {
+    tokenize_inner(sql, regs, trie, dialect, resume, false).unwrap_or_default()
+}
+
+/// stop at the first unrecognized character
+pub fn try_tokenize_internal<'a>(sql: &'a str, regs: &[RegexToken], trie: &TokenTypeTrie, dialect: &dyn Dialect) -> Result<Vec<Token<'a>>, LexError> {
+    tokenize_inner(sql, regs, trie, dialect, &Resume::default(), true)
+}
+
+fn tokenize_inner<'a>(sql: &'a str, regs: &[RegexToken], trie: &TokenTypeTrie, dialect: &dyn Dialect, resume: &Resume, strict: bool) -> Result<Vec<Token<'a>>, LexError> {
     let mut tokens = vec![];
     let mut index = 0;
+    let (mut line, mut column) = (resume.line, resume.column);
+    let offset = resume.offset;
     let sql_len = sql.len();
+    let mut client = if dialect.delimiter_command() { Some(resume.client.clone()) } else { None };
+    // a needle rule only applies while its needle occurs in the rest of the input
+    let lower = sql.to_ascii_lowercase();
+    let needles = regs.iter().map(|rt| rt.needle.map(|n| lower.rfind(n))).collect::<Vec<_>>();
     while index < sql_len {
+        if let Some((typ, len)) = client.as_mut().and_then(|c| c.lex(&sql[index..])) {
+            let v = &sql[index..index+len];
+            let mut t = Token::new(typ, v);
+            t.span = Span::new(offset+index, offset+index+len, line, column);
+            (line, column) = advance_position(v, line, column);
+            if let Some(c) = client.as_mut() { c.update(&t) }
+            tokens.push(t);
+            index += len;
+            continue
+        }
         let mut forawrd = 0;
-        for rt in regs {
-            let i = index.saturating_sub(rt.backward);
+        for (rt, needle) in regs.iter().zip(needles.iter()) {
+            if index < rt.backward {
+                continue
+            }
+            let i = index - rt.backward;
+            if !sql.is_char_boundary(i) {
+                continue
+            }
             let t = &sql[i..];
-            if rt.needle.is_some() && !t.to_lowercase().contains(rt.needle.unwrap()) {
+            if rt.typ != TokenType::KeywordRaw && !rt.first_bytes[t.as_bytes()[0] as usize] {
                 continue
             }
-            // let now = std::time::Instant::now();
+            if let Some(last) = needle {
+                if last.map(|pos| pos < i).unwrap_or(true) {
+                    continue
+                }
+            }
             let mut token_type = rt.typ.clone();
             let opt = match rt.capture {
                 Some(i) => rt.reg.captures(t).map(|c| c.get(i)).flatten().map(|m| m.range()),
                 None => if rt.shortest { rt.reg.shortest_match(t).map(|pos| std::ops::Range { start: 0, end: pos }) }
                     else if rt.typ == TokenType::KeywordRaw {
-                        let upper = t.to_uppercase();
-                        if let Some((pos, typ)) = trie.match_token(&upper) {
-                            // println!("typ: {:?} v: {} t: {}", typ, &t[0..pos], t);
+                        if let Some((pos, typ)) = trie.match_token_with(t, dialect.identifier_chars()) {
                             if let Some(t) = typ { token_type = t; }
                             Some(std::ops::Range{ start: 0, end: pos })
                         } else {
-                            // println!("t: {}", t);
                             token_type = TokenType::Name;
                             rt.reg.find(t).map(|m| m.range())
                         }
+                    } else if rt.typ == TokenType::StringDollar {
+                        rt.reg.find(t).and_then(|m| match_dollar_quoted(t, m.as_str()))
+                    } else if rt.typ == TokenType::CommentMultiline && dialect.nested_comments() {
+                        match_nested_comment(t).or_else(|| rt.reg.find(t).map(|m| m.range()))
                     } else {
                         rt.reg.find(t).map(|m| m.range())
                     },
             };
-            // let elapsed = now.elapsed().as_micros();
-            // if elapsed > 100 {
-            //     println!("elapsed: {} {}", elapsed, rt.reg.as_str());
-            // }
             if opt.is_none() {
                 continue
             }
@@ -188,23 +544,253 @@ pub fn tokenize_internal(sql: &str, regs: &[RegexToken], trie: &TokenTypeTrie) -
             if r.start != rt.backward {
                 continue
             }
-            // println!("matched {}", rt.reg.as_str());
             let v = &t[r.start..r.end];
             forawrd = v.len();
             let typ = match rt.typ {
-                TokenType::KeywordRaw => is_keyword(v),
+                TokenType::KeywordRaw => dialect_keyword(dialect, v),
                 _ => token_type
             };
-            let t = Token::new(typ, v);
+            let mut t = Token::new(typ, v);
+            t.span = Span::new(offset+index, offset+index+v.len(), line, column);
+            (line, column) = advance_position(v, line, column);
             tokens.push(t);
             break;
         }
         if forawrd == 0 {
-            break;
+            let unexpected = sql[index..].chars().next().unwrap();
+            if strict {
+                return Err(LexError { offset: offset+index, line, column, unexpected })
+            }
+            let v = &sql[index..index+unexpected.len_utf8()];
+            forawrd = v.len();
+            let mut t = Token::new(TokenType::Error, v);
+            t.span = Span::new(offset+index, offset+index+v.len(), line, column);
+            (line, column) = advance_position(v, line, column);
+            tokens.push(t);
+        }
+        if let (Some(c), Some(t)) = (client.as_mut(), tokens.last()) {
+            c.update(t);
         }
         index += forawrd
     };
-    tokens
+    Ok(tokens)
+}
+
+/// lexer state at a statement boundary, a long script is lexed piece by piece from there
+#[derive(Debug, Clone)]
+pub(crate) struct Resume {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+    client: ClientDelimiter,
+}
+
+impl Default for Resume {
+    fn default() -> Self {
+        Self { offset: 0, line: 1, column: 1, client: ClientDelimiter::default() }
+    }
+}
+
+impl Resume {
+
+    /// move past the tokens of complete statements
+    pub fn advance(&mut self, tokens: &[Token]) {
+        for idx in 0..tokens.len() {
+            self.client.replay(&tokens[idx..]);
+        }
+        if let Some(last) = tokens.last() {
+            self.offset = last.span.end;
+            (self.line, self.column) = advance_position(last.value(), last.span.line, last.span.column);
+        }
+    }
+
+    /// terminator set by the last `DELIMITER` command
+    pub fn delimiter(&self) -> Option<String> {
+        self.client.delimiter.clone()
+    }
+
+    pub fn line_start(&self) -> bool {
+        self.client.line_start
+    }
+}
+
+// state of the mysql `DELIMITER` client command
+#[derive(Debug, Clone)]
+struct ClientDelimiter {
+    delimiter: Option<String>,
+    line_start: bool,
+    // nothing but whitespace and comments since the last terminator
+    stmt_start: bool,
+    // depth of parentheses in the current statement
+    depth: usize,
+    expect_arg: bool,
+}
+
+impl Default for ClientDelimiter {
+    fn default() -> Self {
+        Self { delimiter: None, line_start: true, stmt_start: true, depth: 0, expect_arg: false }
+    }
+}
+
+impl ClientDelimiter {
+
+    // the command is only recognized as the whole line at the start of a statement
+    fn is_command_start(&self) -> bool {
+        self.line_start && self.stmt_start && self.depth == 0
+    }
+
+    // `DELIMITER` command, its argument and the custom delimiter are lexed here
+    fn lex(&mut self, t: &str) -> Option<(TokenType, usize)> {
+        if self.expect_arg {
+            if t.starts_with(' ') || t.starts_with('\t') {
+                return None
+            }
+            self.expect_arg = false;
+            let len = t.find(char::is_whitespace).unwrap_or(t.len());
+            self.delimiter = if &t[..len] == ";" { None } else { Some(t[..len].to_string()) };
+            return Some((TokenType::Punctuation, len))
+        }
+        if self.is_command_start() && is_delimiter_command(t) {
+            self.expect_arg = true;
+            return Some((TokenType::Keyword, 9))
+        }
+        match &self.delimiter {
+            Some(d) if t.starts_with(d.as_str()) => Some((TokenType::Punctuation, d.len())),
+            _ => None,
+        }
+    }
+
+    // same state changes as lex, from the tokens lexed before starting with the current token
+    fn replay(&mut self, tokens: &[Token]) {
+        let token = &tokens[0];
+        if self.expect_arg && token.typ != TokenType::Whitespace {
+            self.expect_arg = false;
+            if token.typ == TokenType::Punctuation {
+                self.delimiter = if token.value() == ";" { None } else { Some(token.value().to_string()) };
+            }
+        } else if self.is_command_start() && delimiter_command_arg(tokens).is_some() {
+            self.expect_arg = true;
+        }
+        self.update(token);
+    }
+
+    fn update(&mut self, token: &Token) {
+        let value = token.value();
+        match token.typ {
+            TokenType::Whitespace | TokenType::Newline | TokenType::CommentSingle | TokenType::CommentMultiline => {},
+            TokenType::Punctuation if Some(value) == self.delimiter.as_deref() || (self.delimiter.is_none() && value == ";") => {
+                self.stmt_start = true;
+                self.depth = 0;
+            },
+            _ => {
+                self.stmt_start = false;
+                match value {
+                    "(" => self.depth += 1,
+                    ")" => self.depth = self.depth.saturating_sub(1),
+                    _ => {},
+                }
+            },
+        }
+        self.line_start = match token.typ {
+            TokenType::Newline => true,
+            TokenType::Whitespace => self.line_start,
+            TokenType::CommentSingle => value.ends_with('\n') || value.ends_with('\r'),
+            _ => false,
+        };
+    }
+}
+
+// `DELIMITER <delimiter>` up to the end of the line
+fn is_delimiter_command(t: &str) -> bool {
+    let rest = match t.get(..9) {
+        Some(word) if word.eq_ignore_ascii_case("DELIMITER") => &t[9..],
+        _ => return false,
+    };
+    let arg = rest.trim_start_matches([' ', '\t']);
+    let len = arg.find(char::is_whitespace).unwrap_or(arg.len());
+    let tail = arg[len..].trim_start_matches([' ', '\t']);
+    arg.len() < rest.len() && len > 0 && (tail.is_empty() || tail.starts_with(['\n', '\r']))
+}
+
+/// index of the argument of the `DELIMITER` command at the start of the tokens,
+/// the command and its argument must be the whole line
+pub(crate) fn delimiter_command_arg(tokens: &[Token]) -> Option<usize> {
+    let command = tokens.first()?;
+    if command.typ != TokenType::Keyword || !command.value().eq_ignore_ascii_case("DELIMITER") {
+        return None
+    }
+    let arg = tokens.iter().skip(1).position(|t| t.typ != TokenType::Whitespace).map(|i| i+1)?;
+    if arg == 1 || tokens[arg].typ != TokenType::Punctuation {
+        return None
+    }
+    match tokens[arg+1..].iter().find(|t| t.typ != TokenType::Whitespace) {
+        None => Some(arg),
+        Some(t) if t.typ == TokenType::Newline => Some(arg),
+        _ => None,
+    }
+}
+
+// $tag$ ... $tag$, body ends at the first closing tag
+fn match_dollar_quoted(t: &str, tag: &str) -> Option<std::ops::Range<usize>> {
+    t[tag.len()..].find(tag).map(|pos| std::ops::Range { start: 0, end: tag.len() + pos + tag.len() })
+}
+
+// /* ... /* ... */ ... */, unbalanced comments are left to the regex
+fn match_nested_comment(t: &str) -> Option<std::ops::Range<usize>> {
+    if !t.starts_with("/*") {
+        return None
+    }
+    let bytes = t.as_bytes();
+    let mut depth = 0;
+    let mut i = 0;
+    while i + 1 < bytes.len() {
+        match (bytes[i], bytes[i+1]) {
+            (b'/', b'*') => { depth += 1; i += 2; },
+            (b'*', b'/') => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    return Some(std::ops::Range { start: 0, end: i })
+                }
+            },
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// offset of the first token left by a string, quoted identifier or comment missing its closing quote,
+/// the quote may be in the text not read yet
+pub(crate) fn unclosed_offset(tokens: &[Token], dialect: &dyn Dialect) -> Option<usize> {
+    let next_starts_with = |idx: usize, c: char| tokens.get(idx+1).map(|t| t.value().starts_with(c)).unwrap_or(false);
+    tokens.iter().enumerate().find(|(idx, token)| {
+        let v = token.value();
+        match token.typ {
+            // a quote the lexer could not match, other unknown characters stay errors whatever follows
+            TokenType::Error => matches!(v, "'" | "\"" | "`"),
+            TokenType::Operator => v == "/" && next_starts_with(*idx, '*'),
+            TokenType::Punctuation => v == "[" && dialect.identifier_quotes().contains(&'['),
+            TokenType::CommentMultiline => dialect.nested_comments() && match_nested_comment(v).map(|r| r.end) != Some(v.len()),
+            TokenType::Name | TokenType::NamePlaceholder => dialect.dollar_quoted_strings() && v.starts_with('$')
+                && (v == "$$" || next_starts_with(*idx, '$')),
+            _ => false,
+        }
+    }).map(|(_, token)| token.span.start)
+}
+
+// line and column after the token value
+pub(crate) fn advance_position(v: &str, mut line: usize, mut column: usize) -> (usize, usize) {
+    let mut chars = v.chars().peekable();
+    while let Some(c) = chars.next() {
+        let is_newline = c == '\n' || (c == '\r' && chars.peek() != Some(&'\n'));
+        if is_newline {
+            line += 1;
+            column = 1;
+        } else if c != '\r' {
+            column += 1;
+        }
+    }
+    (line, column)
 }
 
 
@@ -41,13 +41,28 @@ mod tokens;
 mod formatter;
 mod filters;
 mod trie;
+mod dialect;
+mod statement;
+mod analysis;
+mod visitor;
+mod query;
+pub mod ast;
 
 
-pub use tokens::TokenType;
-pub use lexer::{Token, TokenList};
+pub use tokens::{TokenType, JoinKind};
+pub use lexer::{Token, TokenList, Span, LexError};
 pub use formatter::{FormatOption};
 pub use engine::grouping::group_tokenlist;
+pub use engine::{StatementStream, StatementSlice};
+pub use statement::{Statement, StatementType};
+pub use analysis::{TableRef, TableAccess, ColumnRef, ColumnLineage, Expression, BinaryOperator, UnaryOperator};
 pub use trie::Trie;
+pub use query::{Query, Combinator, Match, SelectorError};
+pub use visitor::{Visitor, VisitorMut, Visit, VisitContext, visit, visit_mut};
+pub use dialect::{
+    Dialect, Placeholder, GenericDialect, AnsiDialect, PostgreSqlDialect, MySqlDialect,
+    SQLiteDialect, MsSqlDialect, OracleDialect, BigQueryDialect, SnowflakeDialect,
+};
 
 /// parse sql
 pub struct Parser {
@@ -67,46 +82,105 @@ impl Parser {
         Self { stack: engine::FilterStack::new() }
     }
 
+    /// parse sql with the rules of a database dialect
+    pub fn with_dialect<D: Dialect + 'static>(dialect: D) -> Self {
+        Self { stack: engine::FilterStack::with_dialect(Box::new(dialect)) }
+    }
+
     /// parse single sql statement
-    pub fn parse(&self, sql: &str) -> Vec<Token> {
+    pub fn parse<'a>(&self, sql: &'a str) -> Vec<Token<'a>> {
         self.stack.run(sql, true)
     }
 
     /// parse multiple sql statements
-    pub fn parse_multi(&self, sql: &str) -> Vec<Vec<Token>> {
+    pub fn parse_multi<'a>(&self, sql: &'a str) -> Vec<Vec<Token<'a>>> {
         self.stack.run_multi(sql, true)
     }
 
-    pub fn parse_no_grouping(&self, sql: &str) -> Vec<Token> {
+    /// parse multiple sql statements into `Statement`s
+    pub fn parse_statements<'a>(&self, sql: &'a str) -> Vec<Statement<'a>> {
+        self.stack.run_multi(sql, true).into_iter().map(Statement::from).collect()
+    }
+
+    pub fn parse_no_grouping<'a>(&self, sql: &'a str) -> Vec<Token<'a>> {
         self.stack.run(sql, false)
     }
 
-    pub fn parse_multi_no_grouping(&self, sql: &str) -> Vec<Vec<Token>> {
+    pub fn parse_multi_no_grouping<'a>(&self, sql: &'a str) -> Vec<Vec<Token<'a>>> {
         self.stack.run_multi(sql, false)
     }
+
+    /// split sql into statements, the tokens are not grouped
+    pub fn split<'a>(&self, sql: &'a str) -> Vec<StatementSlice<'a>> {
+        self.stack.split(sql)
+    }
+
+    /// parse the statements of a script read from `reader` one at a time,
+    /// a script larger than memory is parsed statement by statement
+    ///
+    /// ```
+    /// use sqlparse::Parser;
+    ///
+    /// let script = "select 1;\nselect 'a\nb';\n";
+    /// let p = Parser::default();
+    /// let stmts = p.parse_stream(script.as_bytes()).collect::<std::io::Result<Vec<_>>>().unwrap();
+    /// assert_eq!(stmts.len(), 2);
+    /// ```
+    pub fn parse_stream<R: std::io::BufRead>(&self, reader: R) -> StatementStream<'_, R> {
+        self.stack.run_stream(reader, true)
+    }
+
+    pub fn parse_stream_no_grouping<R: std::io::BufRead>(&self, reader: R) -> StatementStream<'_, R> {
+        self.stack.run_stream(reader, false)
+    }
+
+    /// parse single sql statement, fail on the first unrecognized character
+    /// instead of emitting a `TokenType::Error` token
+    pub fn try_parse<'a>(&self, sql: &'a str) -> Result<Vec<Token<'a>>, LexError> {
+        self.stack.try_run(sql, true)
+    }
+
+    /// parse multiple sql statements, fail on the first unrecognized character
+    pub fn try_parse_multi<'a>(&self, sql: &'a str) -> Result<Vec<Vec<Token<'a>>>, LexError> {
+        self.stack.try_run_multi(sql, true)
+    }
 }
 
 /// parse sql into tokens,
 /// only for test
-pub fn parse(sql: &str) -> Vec<Token> {
+pub fn parse(sql: &str) -> Vec<Token<'_>> {
     let stack = engine::FilterStack::new();
     stack.run(sql, true)
 }
 
 /// parse multiple sqls into tokens,
 /// only for test
-pub fn parse_multi(sql: &str) -> Vec<Vec<Token>> {
+pub fn parse_multi(sql: &str) -> Vec<Vec<Token<'_>>> {
     let stack = engine::FilterStack::new();
     stack.run_multi(sql, true)
 }
 
 /// parse sql into grouped tokens,
 /// only for test
-pub fn parse_no_grouping(sql: &str) -> Vec<Token> {
+pub fn parse_no_grouping(sql: &str) -> Vec<Token<'_>> {
     let stack = engine::FilterStack::new();
     stack.run(sql, false)
 }
 
+/// split sql into statements with their text, byte range, lines and terminator
+///
+/// ```
+/// let stmts = sqlparse::split("select 1;\n\nselect 2");
+/// assert_eq!(stmts[0].text, "select 1;");
+/// assert_eq!(stmts[0].terminator, Some(";"));
+/// assert_eq!(stmts[1].range, 11..19);
+/// assert_eq!(stmts[1].lines, 3..=3);
+/// ```
+pub fn split(sql: &str) -> Vec<StatementSlice<'_>> {
+    let stack = engine::FilterStack::new();
+    stack.split(sql)
+}
+
 /// format sql with multiple options
 pub struct Formatter {
     stack: engine::FilterStack,
@@ -125,13 +199,18 @@ impl Formatter {
         Self { stack }
     }
 
+    /// format sql with the rules of a database dialect
+    pub fn with_dialect<D: Dialect + 'static>(dialect: D) -> Self {
+        Self { stack: engine::FilterStack::with_dialect(Box::new(dialect)) }
+    }
+
     /// do not use this function repeatly
     pub fn format(&mut self, mut sql: &str, options: &mut formatter::FormatOption) -> String {
         formatter::validate_options(options);
         formatter::build_filter_stack(&mut self.stack, options);
         if options.strip_whitespace { sql = sql.trim(); };
         let tokens = self.stack.format(sql, options.grouping);
-        tokens.iter().map(|token| token.iter().map(|t| t.value.as_str()).collect::<String>()).collect::<Vec<_>>().join("\n")
+        tokens.iter().map(|token| Token::new_value(token)).collect::<Vec<_>>().join("\n")
     }
 
     pub fn build_filters(&mut self, options: &mut formatter::FormatOption) {
@@ -141,7 +220,7 @@ impl Formatter {
 
     pub fn format_sql(&mut self, sql: &str, options: &formatter::FormatOption) -> String {
         let tokens = self.stack.format(sql, options.grouping);
-        tokens.iter().map(|token| token.iter().map(|t| t.value.as_str()).collect::<String>()).collect::<Vec<_>>().join("\n")
+        tokens.iter().map(|token| Token::new_value(token)).collect::<Vec<_>>().join("\n")
     }
 }
 
@@ -156,7 +235,7 @@ pub fn format(mut sql: &str, options: &mut formatter::FormatOption) -> String {
     // for token in &tokens{
     //     println!("{:?}", token);
     // }
-    tokens.iter().map(|token| token.iter().map(|t| t.value.as_str()).collect::<String>()).collect::<Vec<_>>().join("\n")
+    tokens.iter().map(|token| Token::new_value(token)).collect::<Vec<_>>().join("\n")
 }
 
 #[cfg(test)]
@@ -179,7 +258,7 @@ mod tests {
         let tokens = parse(sql);
         // let tokens = parse_no_grouping(sql);
         for token in tokens {
-            println!("{:?} {}", token.typ, token.value);
+            println!("{:?} {}", token.typ, token.value());
         }
     }
 
@@ -0,0 +1,347 @@
+//! Find grouped tokens with selectors
+//!
+//! ```
+//! use sqlparse::{Parser, Query, TokenType};
+//!
+//! let stmts = Parser::default().parse_statements("select count(*) from t where a = 1 and b = 2");
+//! let tokens = &stmts[0].tokens;
+//!
+//! let comparisons = tokens.select("Where > Comparison").unwrap();
+//! assert_eq!(comparisons.iter().map(|m| m.token.value()).collect::<Vec<_>>(), vec!["a = 1", "b = 2"]);
+//!
+//! let count = tokens.query(&Query::descendant(TokenType::Function).with_name("COUNT"));
+//! assert_eq!(tokens.token_at(&count[0].path).map(|t| t.value()), Some("count(*)"));
+//! ```
+
+use std::fmt;
+use crate::lexer::{Token, TokenList};
+use crate::tokens::TokenType;
+use crate::visitor::{visit, Visit, VisitContext, Visitor};
+
+/// how a step of a query relates to the step before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Combinator {
+    /// any level below, the first step matches at any level
+    Descendant,
+    /// one level below, the first step matches top level tokens
+    Child,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Step {
+    combinator: Combinator,
+    // any type if None
+    typ: Option<TokenType>,
+    name: Option<String>,
+    value: Option<String>,
+}
+
+impl Step {
+
+    fn new(combinator: Combinator, typ: Option<TokenType>) -> Self {
+        Self { combinator, typ, name: None, value: None }
+    }
+
+    fn matches(&self, token: &Token) -> bool {
+        self.typ.as_ref().map(|typ| *typ == token.typ).unwrap_or(true) &&
+            self.name.as_ref().map(|name| token.get_name().map(|n| n.eq_ignore_ascii_case(name)).unwrap_or(false)).unwrap_or(true) &&
+            self.value.as_ref().map(|value| token.value().eq_ignore_ascii_case(value)).unwrap_or(true)
+    }
+}
+
+/// a path of steps from the top level tokens to the selected tokens, build it with
+/// `Query::descendant` and `Query::child` or parse a selector
+///
+/// selector syntax: steps are token type names or `*`, separated by whitespace for a descendant
+/// or by `>` for a child, each step can be followed by `[name=...]` or `[value=...]`
+///
+/// ```
+/// use sqlparse::Parser;
+///
+/// let stmts = Parser::default().parse_statements("select count(*) from t where a = 1");
+/// let tokens = &stmts[0].tokens;
+/// let value = |selector| tokens.select(selector).unwrap().iter().map(|m| m.token.value()).collect::<Vec<_>>();
+/// assert_eq!(value("Where > Comparison"), ["a = 1"]);
+/// assert_eq!(value("Function[name=count]"), ["count(*)"]);
+/// assert_eq!(value("> KeywordDML[value=select]"), ["select"]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    steps: Vec<Step>,
+}
+
+impl Query {
+
+    /// tokens of the type at any level
+    pub fn descendant(typ: TokenType) -> Self {
+        Self { steps: vec![Step::new(Combinator::Descendant, Some(typ))] }
+    }
+
+    /// top level tokens of the type
+    pub fn child(typ: TokenType) -> Self {
+        Self { steps: vec![Step::new(Combinator::Child, Some(typ))] }
+    }
+
+    /// tokens of the type at any level below the tokens selected so far
+    pub fn then_descendant(mut self, typ: TokenType) -> Self {
+        self.steps.push(Step::new(Combinator::Descendant, Some(typ)));
+        self
+    }
+
+    /// children of the type of the tokens selected so far
+    pub fn then_child(mut self, typ: TokenType) -> Self {
+        self.steps.push(Step::new(Combinator::Child, Some(typ)));
+        self
+    }
+
+    /// keep tokens whose name (`Token::get_name`) is `name`, ignoring case
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.last_step().name = Some(name.to_string());
+        self
+    }
+
+    /// keep tokens whose value is `value`, ignoring case
+    pub fn with_value(mut self, value: &str) -> Self {
+        self.last_step().value = Some(value.to_string());
+        self
+    }
+
+    /// parse a selector
+    pub fn parse(selector: &str) -> Result<Self, SelectorError> {
+        SelectorParser { selector, pos: 0 }.parse()
+    }
+
+    fn last_step(&mut self) -> &mut Step {
+        self.steps.last_mut().unwrap()
+    }
+
+    // the steps match the token below the ancestors
+    fn matches(steps: &[Step], token: &Token, ancestors: &[&Token]) -> bool {
+        let (step, steps) = match steps.split_last() {
+            Some(s) => s,
+            None => return true,
+        };
+        if !step.matches(token) {
+            return false
+        }
+        match step.combinator {
+            Combinator::Child if steps.is_empty() => ancestors.is_empty(),
+            Combinator::Child => ancestors.split_last()
+                .map(|(parent, ancestors)| Self::matches(steps, parent, ancestors))
+                .unwrap_or(false),
+            Combinator::Descendant if steps.is_empty() => true,
+            Combinator::Descendant => (0..ancestors.len()).rev()
+                .any(|i| Self::matches(steps, ancestors[i], &ancestors[..i])),
+        }
+    }
+}
+
+impl std::str::FromStr for Query {
+    type Err = SelectorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// a selected token and the indexes leading to it, one per level
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match<'t, 'a> {
+    pub path: Vec<usize>,
+    pub token: &'t Token<'a>,
+}
+
+/// error of an invalid selector
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectorError {
+    pub message: String,
+    /// byte offset in the selector
+    pub pos: usize,
+}
+
+impl fmt::Display for SelectorError {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.message, self.pos)
+    }
+}
+
+impl std::error::Error for SelectorError {}
+
+struct SelectorParser<'s> {
+    selector: &'s str,
+    pos: usize,
+}
+
+impl SelectorParser<'_> {
+
+    fn parse(mut self) -> Result<Query, SelectorError> {
+        let mut steps: Vec<Step> = vec![];
+        loop {
+            let skipped = self.skip_whitespace();
+            let combinator = if self.eat('>') {
+                self.skip_whitespace();
+                Combinator::Child
+            } else if steps.is_empty() || skipped {
+                Combinator::Descendant
+            } else if self.pos == self.selector.len() {
+                break
+            } else {
+                return Err(self.error("expected whitespace or '>'"))
+            };
+            if self.pos == self.selector.len() {
+                if steps.is_empty() {
+                    return Err(self.error("empty selector"))
+                }
+                if combinator == Combinator::Child {
+                    return Err(self.error("expected a token type"))
+                }
+                break
+            }
+            let mut step = Step::new(combinator, self.token_type()?);
+            while self.eat('[') {
+                self.attribute(&mut step)?;
+            }
+            steps.push(step);
+        }
+        Ok(Query { steps })
+    }
+
+    fn rest(&self) -> &str {
+        &self.selector[self.pos..]
+    }
+
+    fn error(&self, message: &str) -> SelectorError {
+        SelectorError { message: message.to_string(), pos: self.pos }
+    }
+
+    fn skip_whitespace(&mut self) -> bool {
+        let rest = self.rest();
+        let n = rest.len() - rest.trim_start().len();
+        self.pos += n;
+        n > 0
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let found = self.rest().starts_with(c);
+        if found {
+            self.pos += c.len_utf8();
+        }
+        found
+    }
+
+    fn take_while<F>(&mut self, f: F) -> &str where F: Fn(char) -> bool {
+        let start = self.pos;
+        let n = self.rest().find(|c| !f(c)).unwrap_or(self.rest().len());
+        self.pos += n;
+        &self.selector[start..self.pos]
+    }
+
+    fn token_type(&mut self) -> Result<Option<TokenType>, SelectorError> {
+        if self.eat('*') {
+            return Ok(None)
+        }
+        let start = self.pos;
+        let name = self.take_while(|c| c.is_ascii_alphanumeric());
+        if name.is_empty() {
+            return Err(self.error("expected a token type"))
+        }
+        match TokenType::from_name(name) {
+            Some(typ) => Ok(Some(typ)),
+            None => {
+                let message = format!("unknown token type {}", name);
+                Err(SelectorError { message, pos: start })
+            },
+        }
+    }
+
+    // `name=...]` or `value=...]`, the value can be quoted
+    fn attribute(&mut self, step: &mut Step) -> Result<(), SelectorError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let key = self.take_while(|c| c.is_ascii_alphabetic()).to_string();
+        self.skip_whitespace();
+        if !self.eat('=') {
+            return Err(self.error("expected '='"))
+        }
+        self.skip_whitespace();
+        let value = match self.rest().chars().next() {
+            Some(quote @ ('\'' | '"')) => {
+                self.pos += 1;
+                let value = self.take_while(|c| c != quote).to_string();
+                if !self.eat(quote) {
+                    return Err(self.error("unterminated string"))
+                }
+                value
+            },
+            _ => self.take_while(|c| c != ']' && !c.is_whitespace()).to_string(),
+        };
+        self.skip_whitespace();
+        if !self.eat(']') {
+            return Err(self.error("expected ']'"))
+        }
+        match key.as_str() {
+            "name" => step.name = Some(value),
+            "value" => step.value = Some(value),
+            _ => return Err(SelectorError { message: format!("unknown attribute {}", key), pos: start }),
+        }
+        Ok(())
+    }
+}
+
+struct QueryVisitor<'q, 't, 'a> {
+    query: &'q Query,
+    ancestors: Vec<&'t Token<'a>>,
+    path: Vec<usize>,
+    matches: Vec<Match<'t, 'a>>,
+}
+
+impl<'t, 'a: 't> Visitor<'t, 'a> for QueryVisitor<'_, 't, 'a> {
+
+    fn pre_visit(&mut self, token: &'t Token<'a>, ctx: &VisitContext) -> Visit {
+        self.ancestors.truncate(ctx.depth());
+        self.path.truncate(ctx.depth());
+        self.path.push(ctx.index);
+        if Query::matches(&self.query.steps, token, &self.ancestors) {
+            self.matches.push(Match { path: self.path.clone(), token });
+        }
+        self.ancestors.push(token);
+        Visit::Continue
+    }
+}
+
+/// tokens matching the query, in the order of the sql
+pub fn query<'t, 'a>(tokens: &'t [Token<'a>], query: &Query) -> Vec<Match<'t, 'a>> {
+    let mut visitor = QueryVisitor { query, ancestors: vec![], path: vec![], matches: vec![] };
+    visit(tokens, &mut visitor);
+    visitor.matches
+}
+
+/// token at the path of a `Match`
+pub fn token_at<'t, 'a>(tokens: &'t [Token<'a>], path: &[usize]) -> Option<&'t Token<'a>> {
+    let (first, path) = path.split_first()?;
+    path.iter().try_fold(tokens.get(*first)?, |token, idx| token.children.tokens.get(*idx))
+}
+
+impl<'a> TokenList<'a> {
+
+    /// see [`Query`]
+    pub fn query<'t>(&'t self, q: &Query) -> Vec<Match<'t, 'a>> {
+        query(&self.tokens, q)
+    }
+
+    /// tokens matching the selector, see [`Query`]
+    pub fn select<'t>(&'t self, selector: &str) -> Result<Vec<Match<'t, 'a>>, SelectorError> {
+        Ok(self.query(&Query::parse(selector)?))
+    }
+
+    pub fn token_at(&self, path: &[usize]) -> Option<&Token<'a>> {
+        token_at(&self.tokens, path)
+    }
+
+    /// the cached values of the groups around the token are not updated, see [`crate::VisitorMut`]
+    pub fn token_at_mut(&mut self, path: &[usize]) -> Option<&mut Token<'a>> {
+        let (first, path) = path.split_first()?;
+        path.iter().try_fold(self.tokens.get_mut(*first)?, |token, idx| token.children.tokens.get_mut(*idx))
+    }
+}
@@ -0,0 +1,155 @@
+//! Grouped sql statements
+//!
+//! ```
+//! use sqlparse::{Parser, StatementType};
+//!
+//! let p = Parser::default();
+//! let stmts = p.parse_statements("with a as (select 1) select * from a; commit;");
+//! assert_eq!(stmts[0].statement_type(), StatementType::Select);
+//! assert_eq!(stmts[1].statement_type(), StatementType::Transaction);
+//! ```
+
+use std::fmt;
+use crate::lexer::{Token, TokenList};
+use crate::tokens::TokenType;
+use crate::analysis::{self, TableRef, ColumnLineage, is_skipped};
+use crate::ast;
+
+/// kind of a statement, from its leading keyword
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StatementType {
+    Select,
+    /// `INSERT`, `REPLACE` and `UPSERT`
+    Insert,
+    Update,
+    Delete,
+    Merge,
+    Create,
+    Alter,
+    Drop,
+    /// `BEGIN`, `START TRANSACTION`, `COMMIT`, `ROLLBACK`, `SAVEPOINT`, `RELEASE`, `END` and `ABORT`
+    Transaction,
+    /// session `SET`
+    Set,
+    Unknown,
+}
+
+impl StatementType {
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StatementType::Select => "SELECT",
+            StatementType::Insert => "INSERT",
+            StatementType::Update => "UPDATE",
+            StatementType::Delete => "DELETE",
+            StatementType::Merge => "MERGE",
+            StatementType::Create => "CREATE",
+            StatementType::Alter => "ALTER",
+            StatementType::Drop => "DROP",
+            StatementType::Transaction => "TRANSACTION",
+            StatementType::Set => "SET",
+            StatementType::Unknown => "UNKNOWN",
+        }
+    }
+
+    // type of a statement starting with the token
+    fn from_token(token: &Token) -> Self {
+        // a BEGIN ... END block is not a transaction
+        if token.typ == TokenType::Begin {
+            return StatementType::Unknown
+        }
+        let word = token.value().split_whitespace().next().unwrap_or("").to_ascii_uppercase();
+        match word.as_str() {
+            "SELECT" => StatementType::Select,
+            "INSERT" | "REPLACE" | "UPSERT" => StatementType::Insert,
+            "UPDATE" => StatementType::Update,
+            "DELETE" => StatementType::Delete,
+            "MERGE" => StatementType::Merge,
+            "CREATE" => StatementType::Create,
+            "ALTER" => StatementType::Alter,
+            "DROP" => StatementType::Drop,
+            "BEGIN" | "START" | "COMMIT" | "ROLLBACK" | "SAVEPOINT" | "RELEASE" | "END" | "ABORT" => StatementType::Transaction,
+            "SET" => StatementType::Set,
+            _ => StatementType::Unknown,
+        }
+    }
+}
+
+impl fmt::Display for StatementType {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// a grouped statement, one result of `Parser::parse_multi`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement<'a> {
+    pub tokens: TokenList<'a>,
+}
+
+impl<'a> From<Vec<Token<'a>>> for Statement<'a> {
+
+    fn from(tokens: Vec<Token<'a>>) -> Self {
+        Self { tokens: TokenList::new(tokens) }
+    }
+}
+
+impl<'a> Statement<'a> {
+
+    pub fn new(tokens: Vec<Token<'a>>) -> Self {
+        Self::from(tokens)
+    }
+
+    /// first token that is not whitespace or a comment
+    pub fn first_token(&self) -> Option<&Token<'a>> {
+        self.tokens.tokens.iter().find(|t| !is_skipped(t))
+    }
+
+    /// kind of the statement, `WITH ...` resolves to the statement after the common table expressions
+    /// and a parenthesized query to the query
+    pub fn statement_type(&self) -> StatementType {
+        statement_type(&self.tokens.tokens)
+    }
+
+    /// tables read or written by the statement, including the tables of subqueries and
+    /// common table expressions, the names of common table expressions are left out
+    pub fn tables(&self) -> Vec<TableRef> {
+        analysis::tables::tables(&self.tokens.tokens)
+    }
+
+    /// source columns of each output column of a `SELECT`, or of each inserted column of
+    /// `INSERT ... SELECT`, empty for other statements
+    pub fn column_lineage(&self) -> Vec<ColumnLineage> {
+        analysis::lineage::column_lineage(&self.tokens.tokens)
+    }
+
+    /// typed view of the statement
+    pub fn ast(&self) -> ast::Statement<'_, 'a> {
+        ast::Statement::from(&self.tokens.tokens[..])
+    }
+}
+
+impl fmt::Display for Statement<'_> {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&Token::new_value(&self.tokens.tokens))
+    }
+}
+
+fn statement_type(tokens: &[Token]) -> StatementType {
+    let idx = match tokens.iter().position(|t| !is_skipped(t)) {
+        Some(idx) => idx,
+        None => return StatementType::Unknown,
+    };
+    let token = &tokens[idx];
+    match token.typ {
+        // the main statement follows the common table expressions
+        TokenType::With => tokens[idx+1..].iter()
+            .find(|t| t.typ == TokenType::KeywordDML)
+            .map(StatementType::from_token)
+            .unwrap_or(StatementType::Unknown),
+        TokenType::Subquery => statement_type(token.children.tokens.get(1..).unwrap_or(&[])),
+        _ => StatementType::from_token(token),
+    }
+}
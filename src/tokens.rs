@@ -1,6 +1,26 @@
 
-#[derive(PartialEq, Debug, Clone)]
-pub enum TokenType {
+// the enum and its name lookup are generated together so they cannot drift apart
+macro_rules! token_types {
+    ($($typ:ident,)*) => {
+        #[derive(PartialEq, Debug, Clone)]
+        pub enum TokenType {
+            $($typ,)*
+        }
+
+        impl TokenType {
+
+            /// type named as the variant, `"Function"`, `"Where"`, ...
+            pub fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    $(stringify!($typ) => Some(TokenType::$typ),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+token_types! {
     Text,
     Whitespace,
     Newline,
@@ -22,6 +42,7 @@ pub enum TokenType {
     String,
     StringSingle,
     StringSymbol,
+    StringDollar, // $$...$$ and $tag$...$tag$
     Number,
     NumberHexadecimal,
     NumberFloat,
@@ -53,10 +74,52 @@ pub enum TokenType {
     Identifier,
     IdentifierList,
     Where,
+    // clauses of a statement
+    From,
+    Join,
+    GroupBy,
+    Having,
+    OrderBy,
+    Limit,
+    Offset,
+    Fetch,
+    Window,
+    Returning,
     Function,
+    Over, // OVER (...) or OVER name of a window function
+    WindowSpec, // parenthesis of a window definition
     Operation,
     TypedLiteral,
     Parenthesis,
+    Subquery, // parenthesis around a query
     SquareBrackets,
     Values,
-}
\ No newline at end of file
+    With,
+    CommonTableExpression,
+}
+
+/// kind of a JOIN clause
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum JoinKind {
+    /// `JOIN` and `INNER JOIN`
+    Inner,
+    /// `LEFT [OUTER] JOIN`
+    Left,
+    /// `RIGHT [OUTER] JOIN`
+    Right,
+    /// `FULL [OUTER] JOIN`
+    Full,
+    Cross,
+    Natural,
+    /// `STRAIGHT_JOIN` of MySQL
+    Straight,
+}
+
+impl TokenType {
+
+    /// group of a statement clause, `FROM`, `JOIN`, `GROUP BY`, ...
+    pub fn is_clause(&self) -> bool {
+        matches!(self, TokenType::From | TokenType::Join | TokenType::GroupBy | TokenType::Having | TokenType::OrderBy |
+            TokenType::Limit | TokenType::Offset | TokenType::Fetch | TokenType::Window | TokenType::Returning)
+    }
+}
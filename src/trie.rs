@@ -108,39 +108,36 @@ pub type TokenTypeTrie = Trie<TokenType>;
 impl TokenTypeTrie {
 
     pub fn match_token(&self, sql: &str) -> Option<(usize, Option<TokenType>)> {
-        let chars = sql.chars();
+        self.match_token_with(sql, &['$', '#'])
+    }
+
+    // byte length and type of the keyword or name at the start of sql, keys are matched case insensitively,
+    // extra are the identifier characters besides unicode XID characters
+    pub fn match_token_with(&self, sql: &str, extra: &[char]) -> Option<(usize, Option<TokenType>)> {
         let mut current = &self.root;
-        for (level, c) in chars.enumerate() {
-            if !current.children.contains_key(&c) {
-                if level < 3 { return match_name(sql) } // min keyword length is 2
-                // https://www.regular-expressions.info/wordboundaries.html
-                let is_end = match c {
-                    ' ' | ';' | ':' | '\n' | '\r' | '(' | ')' => true,
-                    _ => false,
-                };
-                return if is_end { Some((level, current.typ.clone())) } else { match_name(sql) }
+        for (level, c) in sql.char_indices() {
+            match current.children.get(&c.to_ascii_uppercase()) {
+                Some(node) => current = node,
+                None => {
+                    if level < 3 { return match_name(sql, extra) } // min keyword length is 2
+                    return if !is_identifier_char(c, extra) { Some((level, current.typ.clone())) } else { match_name(sql, extra) }
+                }
             }
-            current = current.children.get(&c).unwrap();
         }
-        if current.is_last { Some((sql.len(), current.typ.clone())) } else { match_name(sql) }
+        if current.is_last { Some((sql.len(), current.typ.clone())) } else { match_name(sql, extra) }
     }
 
 }
 
-fn match_name(sql: &str) -> Option<(usize, Option<TokenType>)> {
-    // match 0-9A-Z_ AND end with space ;
-    let chars = sql.chars();
-    let mut last_level = 0;
-    let mut last_char = ';';
-    for (level, c) in chars.enumerate() {
-       let is_word_character = (c >= 'A' && c <= 'Z') || (c >= 'a' && c <= 'z') || (c >= '0' && c <= '9') || c == '_';
-       last_level = level;
-       last_char = c;
-       if !is_word_character { break; }
-    }
-    // println!("t: {}, last_level {}, last_char {}", sql, last_level, last_char);
-    if last_level > 0 && (last_char == ' ' || last_char == ';' || last_char == ',' || last_char == ')' || last_char == '\n') { 
-        Some((last_level, Some(TokenType::Name))) } else { None }
+// unicode XID_Continue, `_` included
+pub fn is_identifier_char(c: char, extra: &[char]) -> bool {
+    unicode_ident::is_xid_continue(c) || extra.contains(&c)
+}
+
+fn match_name(sql: &str, extra: &[char]) -> Option<(usize, Option<TokenType>)> {
+    // match identifier characters followed by a boundary
+    let end = sql.char_indices().find(|(_, c)| !is_identifier_char(*c, extra)).map(|(i, _)| i).unwrap_or(sql.len());
+    if end > 0 { Some((end, Some(TokenType::Name))) } else { None }
 }
 
 
@@ -213,6 +210,22 @@ mod tests {
         assert_eq!(typ.unwrap(), TokenType::Keyword);
     }
 
+    #[test]
+    fn test_match_token_unicode() {
+        let mut t = Trie::default();
+        t.insert("SELECT", TokenType::KeywordDML);
+        t.insert("FROM", TokenType::Keyword);
+        let sql = "select*from";
+        assert_eq!(t.match_token(sql), Some((6, Some(TokenType::KeywordDML))));
+        let sql = "selectété from";
+        let (pos, typ) = t.match_token(sql).unwrap();
+        assert_eq!(&sql[0..pos], "selectété");
+        assert_eq!(typ, Some(TokenType::Name));
+        let sql = "名前 = 1";
+        assert_eq!(t.match_token(sql), Some(("名前".len(), Some(TokenType::Name))));
+        assert_eq!(t.match_token_with("a$b", &[]), Some((1, Some(TokenType::Name))));
+    }
+
     #[test]
     fn test_trie1() {
         let mut t = Trie::<TokenType>::default();
@@ -0,0 +1,175 @@
+//! Walk grouped tokens with pre and post hooks
+//!
+//! ```
+//! use sqlparse::{Parser, TokenType, Visit, VisitContext, VisitorMut};
+//!
+//! // uppercase the names of functions
+//! struct UpperFunctions;
+//!
+//! impl VisitorMut for UpperFunctions {
+//!     fn types(&self) -> &[TokenType] { &[TokenType::Name] }
+//!
+//!     fn pre_visit(&mut self, token: &mut sqlparse::Token<'_>, ctx: &VisitContext) -> Visit {
+//!         if ctx.parents.ends_with(&[TokenType::Function, TokenType::Identifier]) && ctx.index == 0 {
+//!             let name = token.value().to_uppercase();
+//!             token.set_value(name);
+//!         }
+//!         Visit::Continue
+//!     }
+//! }
+//!
+//! let mut tokens = Parser::default().parse("select count(a) from t");
+//! sqlparse::visit_mut(&mut tokens, &mut UpperFunctions);
+//! assert_eq!(tokens.iter().map(|t| t.value()).collect::<String>(), "select COUNT(a) from t");
+//! ```
+
+use crate::lexer::{Token, TokenList};
+use crate::tokens::TokenType;
+
+/// what the walk does after a hook
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visit {
+    Continue,
+    /// the children of the token are not visited, only for `pre_visit`
+    SkipChildren,
+    /// end the walk
+    Stop,
+}
+
+/// position of a visited token
+#[derive(Debug, Clone, Copy)]
+pub struct VisitContext<'c> {
+    /// types of the groups around the token, the innermost last
+    pub parents: &'c [TokenType],
+    /// index of the token in its group
+    pub index: usize,
+}
+
+impl VisitContext<'_> {
+
+    /// number of groups around the token, 0 for a top level token
+    pub fn depth(&self) -> usize {
+        self.parents.len()
+    }
+
+    pub fn parent(&self) -> Option<&TokenType> {
+        self.parents.last()
+    }
+}
+
+/// hooks for reading grouped tokens, the tokens outlive the visitor so they can be collected
+pub trait Visitor<'t, 'a: 't> {
+
+    /// types of the tokens the hooks are called for, every token if empty
+    fn types(&self) -> &[TokenType] {
+        &[]
+    }
+
+    /// called before the children of the token
+    fn pre_visit(&mut self, _token: &'t Token<'a>, _ctx: &VisitContext) -> Visit {
+        Visit::Continue
+    }
+
+    /// called after the children of the token
+    fn post_visit(&mut self, _token: &'t Token<'a>, _ctx: &VisitContext) -> Visit {
+        Visit::Continue
+    }
+}
+
+/// hooks for rewriting grouped tokens, the values of the groups around a changed token are updated by the walk
+pub trait VisitorMut {
+
+    /// types of the tokens the hooks are called for, every token if empty
+    fn types(&self) -> &[TokenType] {
+        &[]
+    }
+
+    /// called before the children of the token, changes to the children are visited
+    fn pre_visit(&mut self, _token: &mut Token<'_>, _ctx: &VisitContext) -> Visit {
+        Visit::Continue
+    }
+
+    /// called after the children of the token
+    fn post_visit(&mut self, _token: &mut Token<'_>, _ctx: &VisitContext) -> Visit {
+        Visit::Continue
+    }
+}
+
+/// visit the tokens and their children depth first
+pub fn visit<'t, 'a, V>(tokens: &'t [Token<'a>], visitor: &mut V) where V: Visitor<'t, 'a> + ?Sized {
+    walk(tokens, visitor, &mut vec![]);
+}
+
+/// visit the tokens and their children depth first, the values of groups are updated after their children
+pub fn visit_mut<V>(tokens: &mut [Token], visitor: &mut V) where V: VisitorMut + ?Sized {
+    walk_mut(tokens, visitor, &mut vec![]);
+}
+
+impl<'a> TokenList<'a> {
+
+    /// see [`visit`]
+    pub fn visit<'t, V>(&'t self, visitor: &mut V) where V: Visitor<'t, 'a> + ?Sized {
+        visit(&self.tokens, visitor)
+    }
+
+    /// see [`visit_mut`]
+    pub fn visit_mut<V>(&mut self, visitor: &mut V) where V: VisitorMut + ?Sized {
+        visit_mut(&mut self.tokens, visitor)
+    }
+}
+
+fn is_hooked(types: &[TokenType], typ: &TokenType) -> bool {
+    types.is_empty() || types.contains(typ)
+}
+
+// true if the walk is stopped
+fn walk<'t, 'a, V>(tokens: &'t [Token<'a>], visitor: &mut V, parents: &mut Vec<TokenType>) -> bool where V: Visitor<'t, 'a> + ?Sized {
+    for (index, token) in tokens.iter().enumerate() {
+        let hooked = is_hooked(visitor.types(), &token.typ);
+        let action = if hooked { visitor.pre_visit(token, &VisitContext { parents, index }) } else { Visit::Continue };
+        match action {
+            Visit::Stop => return true,
+            Visit::SkipChildren => {},
+            Visit::Continue => {
+                parents.push(token.typ.clone());
+                let stopped = walk(&token.children.tokens, visitor, parents);
+                parents.pop();
+                if stopped {
+                    return true
+                }
+            },
+        }
+        if hooked && visitor.post_visit(token, &VisitContext { parents, index }) == Visit::Stop {
+            return true
+        }
+    }
+    false
+}
+
+fn walk_mut<V>(tokens: &mut [Token], visitor: &mut V, parents: &mut Vec<TokenType>) -> bool where V: VisitorMut + ?Sized {
+    for (index, token) in tokens.iter_mut().enumerate() {
+        let hooked = is_hooked(visitor.types(), &token.typ);
+        let mut stopped = false;
+        let action = if hooked { visitor.pre_visit(token, &VisitContext { parents, index }) } else { Visit::Continue };
+        match action {
+            Visit::Stop => stopped = true,
+            Visit::SkipChildren => {},
+            Visit::Continue => {
+                parents.push(token.typ.clone());
+                stopped = walk_mut(&mut token.children.tokens, visitor, parents);
+                parents.pop();
+            },
+        }
+        if hooked && !stopped {
+            stopped = visitor.post_visit(token, &VisitContext { parents, index }) == Visit::Stop;
+        }
+        // the cached value of a group is stale once anything below it changed
+        if token.is_group() {
+            token.update_value();
+        }
+        if stopped {
+            return true
+        }
+    }
+    false
+}
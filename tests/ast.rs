@@ -0,0 +1,132 @@
+use sqlparse::{Parser, Token, JoinKind, Statement, ast};
+
+fn statements(sql: &str) -> Vec<Statement<'_>> {
+    Parser::default().parse_statements(sql)
+}
+
+fn values(tokens: &[&Token]) -> Vec<String> {
+    tokens.iter().map(|t| t.value().to_string()).collect()
+}
+
+#[test]
+fn test_ast_select() {
+    let sql = "select distinct a, b as c from t1, t2 x join t3 on t3.id = x.id left join t4 using (id) where a > 1 group by a, b having count(*) > 1 order by a desc limit 10 offset 2";
+    let stmts = statements(sql);
+    let select = match stmts[0].ast() {
+        ast::Statement::Select(select) => select,
+        stmt => panic!("{:?}", stmt),
+    };
+    assert!(select.distinct);
+    assert_eq!(values(&select.projections), vec!["a", "b as c"]);
+    assert_eq!(select.projections[1].get_alias().as_deref(), Some("c"));
+    assert_eq!(values(&select.from), vec!["t1", "t2 x"]);
+    assert_eq!(select.joins.len(), 2);
+    assert_eq!(select.joins[0].kind, JoinKind::Inner);
+    assert!(matches!(&select.joins[0].constraint, ast::JoinConstraint::On(ast::Condition::Expression(e)) if e.to_string() == "(t3.id = x.id)"));
+    assert_eq!(select.joins[1].kind, JoinKind::Left);
+    assert_eq!(select.joins[1].relation.map(|t| t.value()), Some("t4"));
+    assert_eq!(select.joins[1].constraint, ast::JoinConstraint::Using(vec!["id"]));
+    assert!(matches!(&select.selection, Some(ast::Condition::Expression(e)) if e.to_string() == "(a > 1)"));
+    assert_eq!(values(&select.group_by), vec!["a", "b"]);
+    assert!(matches!(&select.having, Some(ast::Condition::Expression(e)) if e.to_string() == "(count(*) > 1)"));
+    assert_eq!(values(&select.order_by), vec!["a desc"]);
+    assert_eq!(select.limit.map(|t| t.value()), Some("10"));
+    assert_eq!(select.offset.map(|t| t.value()), Some("2"));
+    assert!(select.raw.is_empty());
+    // the nodes reference the tokens of the statement
+    assert_eq!(select.tokens.iter().map(|t| t.value()).collect::<String>(), sql);
+    assert!(std::ptr::eq(select.joins[0].token, &stmts[0].tokens.tokens[8]));
+}
+
+#[test]
+fn test_ast_raw() {
+    let stmts = statements("with c as (select 1) select top 10 null from c union select 2");
+    let select = match stmts[0].ast() {
+        ast::Statement::Select(select) => select,
+        stmt => panic!("{:?}", stmt),
+    };
+    assert!(select.with.is_some());
+    assert_eq!(values(&select.projections), vec!["null"]);
+    assert_eq!(values(&select.from), vec!["c"]);
+    assert_eq!(select.raw.iter().map(|r| r.to_string()).collect::<Vec<_>>(), vec!["top 10", "union select 2"]);
+
+    let stmts = statements("select * from t where a = 1 and");
+    match stmts[0].ast() {
+        ast::Statement::Select(select) => assert!(matches!(select.selection, Some(ast::Condition::Raw(r)) if r.to_string() == "a = 1 and")),
+        stmt => panic!("{:?}", stmt),
+    }
+    let stmts = statements("drop table t;");
+    assert!(matches!(stmts[0].ast(), ast::Statement::Raw(r) if r.to_string() == "drop table t;"));
+}
+
+#[test]
+fn test_ast_dml() {
+    let stmts = statements("insert into s.t (a, b) values (1, 2), (3, 4) returning id; insert into t select x from u; update t set a = 1, b = b + 1 from u where t.id = u.id; delete from t using u where t.id = u.id");
+    match stmts[0].ast() {
+        ast::Statement::Insert(insert) => {
+            let table = insert.table.unwrap();
+            assert_eq!(table.to_string(), "s.t");
+            assert_eq!((table.parent().as_deref(), table.name().as_deref()), (Some("s"), Some("t")));
+            assert_eq!(values(&insert.columns), vec!["a", "b"]);
+            assert_eq!(values(&insert.values), vec!["(1, 2)", "(3, 4)"]);
+            assert!(insert.query.is_none());
+            assert_eq!(insert.raw.iter().map(|r| r.to_string()).collect::<Vec<_>>(), vec!["returning id;"]);
+        },
+        stmt => panic!("{:?}", stmt),
+    }
+    match stmts[1].ast() {
+        ast::Statement::Insert(insert) => {
+            assert_eq!(insert.table.unwrap().to_string(), "t");
+            assert_eq!(values(&insert.query.unwrap().from), vec!["u"]);
+        },
+        stmt => panic!("{:?}", stmt),
+    }
+    match stmts[2].ast() {
+        ast::Statement::Update(update) => {
+            assert_eq!(update.table.map(|t| t.value()), Some("t"));
+            assert_eq!(values(&update.assignments), vec!["a = 1", "b = b + 1"]);
+            assert_eq!(values(&update.from), vec!["u"]);
+            assert!(matches!(update.selection, Some(ast::Condition::Expression(_))));
+        },
+        stmt => panic!("{:?}", stmt),
+    }
+    match stmts[3].ast() {
+        ast::Statement::Delete(delete) => {
+            assert_eq!(delete.table.map(|t| t.value()), Some("t"));
+            assert_eq!(values(&delete.using), vec!["u"]);
+            assert!(delete.selection.is_some());
+        },
+        stmt => panic!("{:?}", stmt),
+    }
+}
+
+#[test]
+fn test_ast_insert_table() {
+    for (sql, table, columns) in [
+        ("insert into t(a) values (1)", "t", vec!["a"]),
+        ("insert into t values (1)", "t", vec![]),
+        ("insert into \"x\".t as q (a) values (1)", "\"x\".t", vec!["a"]),
+    ] {
+        let stmts = statements(sql);
+        let ast::Statement::Insert(insert) = stmts[0].ast() else { panic!("{}", sql) };
+        assert_eq!(insert.table.map(|t| t.to_string()).as_deref(), Some(table));
+        assert_eq!(values(&insert.columns), columns);
+    }
+}
+
+#[test]
+fn test_ast_create_table() {
+    let stmts = statements("create table if not exists s.t (id int primary key, name varchar(10) not null, constraint pk unique (name))");
+    let create = match stmts[0].ast() {
+        ast::Statement::CreateTable(create) => create,
+        stmt => panic!("{:?}", stmt),
+    };
+    assert!(create.if_not_exists);
+    assert_eq!(create.name.and_then(|t| t.get_real_name()).as_deref(), Some("t"));
+    let columns = create.columns.iter().map(|c| (c.name.value(), values(&c.data_type), values(&c.options))).collect::<Vec<_>>();
+    assert_eq!(columns, vec![
+        ("id", vec!["int".to_string()], vec!["primary".to_string(), "key".to_string()]),
+        ("name", vec!["varchar(10)".to_string()], vec!["not null".to_string()]),
+    ]);
+    assert_eq!(create.constraints.iter().map(|r| r.to_string()).collect::<Vec<_>>(), vec!["constraint pk unique (name)"]);
+}
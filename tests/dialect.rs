@@ -0,0 +1,142 @@
+use sqlparse::{
+    TokenType, Parser, Formatter, FormatOption, AnsiDialect, PostgreSqlDialect, MySqlDialect,
+    SQLiteDialect, MsSqlDialect, BigQueryDialect, SnowflakeDialect, Dialect,
+};
+
+#[test]
+fn test_dialect_keyword_table() {
+    let sql = "select line, point from shapes";
+    let p = Parser::default();
+    let tokens = p.parse_no_grouping(sql);
+    assert_eq!(tokens[2].typ, TokenType::Keyword);
+    let p = Parser::with_dialect(AnsiDialect);
+    let tokens = p.parse_no_grouping(sql);
+    assert_eq!(tokens[2].typ, TokenType::Name);
+    assert_eq!(tokens[5].typ, TokenType::Name);
+    let p = Parser::with_dialect(PostgreSqlDialect);
+    let tokens = p.parse_no_grouping(sql);
+    assert_eq!(tokens[2].typ, TokenType::Keyword);
+
+    let p = Parser::with_dialect(MsSqlDialect);
+    let tokens = p.parse_no_grouping("select top 10 cast(price as money) from t");
+    assert_eq!(tokens[2].typ, TokenType::Keyword);
+    let tokens = p.parse_no_grouping("cast(price as money)");
+    assert_eq!(tokens.iter().find(|t| t.value() == "money").unwrap().typ, TokenType::NameBuiltin);
+}
+
+#[test]
+fn test_dialect_identifier_quotes() {
+    let sql = "select `a`, \"b\" from t";
+    let tokens = Parser::with_dialect(MySqlDialect).parse_no_grouping(sql);
+    assert_eq!(tokens[2].typ, TokenType::Name);
+    assert_eq!(tokens[5].typ, TokenType::String);
+    let tokens = Parser::with_dialect(PostgreSqlDialect).parse_no_grouping(sql);
+    assert_eq!(tokens[2].typ, TokenType::Error);
+    assert_eq!(tokens.iter().find(|t| t.value() == "\"b\"").unwrap().typ, TokenType::StringSymbol);
+    let tokens = Parser::with_dialect(BigQueryDialect).parse_no_grouping("select `project.dataset.table`");
+    assert_eq!(tokens[2].typ, TokenType::Name);
+}
+
+#[test]
+fn test_dialect_string_escapes() {
+    let sql = r"select 'a\', 'b'";
+    let tokens = Parser::with_dialect(AnsiDialect).parse_no_grouping(sql);
+    assert_eq!(tokens[2].value(), r"'a\'");
+    assert_eq!(tokens[5].value(), "'b'");
+    let tokens = Parser::with_dialect(MySqlDialect).parse_no_grouping(sql);
+    assert_eq!(tokens[2].value(), r"'a\', '");
+}
+
+#[test]
+fn test_dialect_prefixed_strings() {
+    let sql = r#"select r'\d+', b"\x01", """multi
+line""", E'x'"#;
+    let tokens = Parser::with_dialect(BigQueryDialect).parse_no_grouping(sql);
+    assert_eq!(tokens[2].value(), r"r'\d+'");
+    assert_eq!(tokens[2].typ, TokenType::StringSingle);
+    assert_eq!(tokens[5].value(), r#"b"\x01""#);
+    assert_eq!(tokens[8].value(), "\"\"\"multi\nline\"\"\"");
+    // E strings are postgres only
+    assert_eq!(tokens[11].value(), "E");
+    let tokens = Parser::with_dialect(PostgreSqlDialect).parse_no_grouping(r"select E'\n'");
+    assert_eq!(tokens[2].value(), r"E'\n'");
+}
+
+#[test]
+fn test_dialect_nested_comments() {
+    let sql = "/* a /* b */ c */ select 1";
+    let tokens = Parser::with_dialect(PostgreSqlDialect).parse_no_grouping(sql);
+    assert_eq!(tokens[0].value(), "/* a /* b */ c */");
+    let tokens = Parser::with_dialect(MySqlDialect).parse_no_grouping(sql);
+    assert_eq!(tokens[0].value(), "/* a /* b */");
+}
+
+#[test]
+fn test_dialect_placeholders() {
+    let cases = vec![
+        (Parser::with_dialect(PostgreSqlDialect), "$1", true),
+        (Parser::with_dialect(PostgreSqlDialect), ":name", false),
+        (Parser::with_dialect(MySqlDialect), "?", true),
+        (Parser::with_dialect(SQLiteDialect), "@name", true),
+        (Parser::with_dialect(BigQueryDialect), "@name", true),
+        (Parser::with_dialect(SnowflakeDialect), ":1", true),
+        (Parser::with_dialect(AnsiDialect), "%s", false),
+    ];
+    for (p, placeholder, expected) in cases {
+        let sql = format!("select * from foo where x = {}", placeholder);
+        let tokens = p.parse_no_grouping(&sql);
+        let is_placeholder = tokens.iter().any(|t| t.typ == TokenType::NamePlaceholder && t.value() == placeholder);
+        assert_eq!(is_placeholder, expected, "{}", placeholder);
+    }
+}
+
+#[test]
+fn test_dialect_name() {
+    let dialects: Vec<Box<dyn Dialect>> = vec![Box::new(AnsiDialect), Box::new(MsSqlDialect)];
+    let names = dialects.iter().map(|d| d.name()).collect::<Vec<_>>();
+    assert_eq!(names, vec!["ansi", "mssql"]);
+}
+
+#[test]
+fn test_dialect_format() {
+    let sql = "select line from shapes";
+    let mut options = FormatOption::default();
+    options.keyword_case = "upper";
+    let formatted = Formatter::default().format(sql, &mut options);
+    assert_eq!(formatted, "SELECT LINE FROM shapes");
+    let mut options = FormatOption::default();
+    options.keyword_case = "upper";
+    let formatted = Formatter::with_dialect(AnsiDialect).format(sql, &mut options);
+    assert_eq!(formatted, "SELECT line FROM shapes");
+}
+
+#[test]
+fn test_tsql_bracket_identifiers_and_variables() {
+    let p = Parser::with_dialect(MsSqlDialect);
+    let tokens = p.parse("select @@ROWCOUNT, @i from [dbo].[My ]]Table] as t");
+    assert_eq!(tokens[2].typ, TokenType::IdentifierList);
+    let names = tokens[2].children.tokens.iter().filter(|t| t.typ == TokenType::Identifier).map(|t| t.value()).collect::<Vec<_>>();
+    assert_eq!(names, vec!["@@ROWCOUNT", "@i"]);
+    let table = &tokens[4].children.tokens[2];
+    assert_eq!(table.typ, TokenType::Identifier);
+    assert_eq!(table.get_parent_name().as_deref(), Some("dbo"));
+    assert_eq!(table.get_real_name().as_deref(), Some("My ]Table"));
+    assert_eq!(table.get_alias().as_deref(), Some("t"));
+}
+
+#[test]
+fn test_tsql_reindent() {
+    let sql = "select [Order Id], @@ROWCOUNT from [dbo].[Orders] where [Id] = @id\nGO\nupdate [dbo].[Orders] set [Total] = @total where [Id] = @id";
+    let mut options = FormatOption::default_reindent();
+    let formatted = Formatter::with_dialect(MsSqlDialect).format(sql, &mut options);
+    assert_eq!(formatted, [
+        "select [Order Id],",
+        "       @@ROWCOUNT",
+        "from [dbo].[Orders]",
+        "where [Id] = @id",
+        "GO",
+        "update [dbo].[Orders]",
+        "set [Total] = @total",
+        "where [Id] = @id",
+    ].join("\n"));
+}
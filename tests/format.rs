@@ -1,4 +1,4 @@
-use sqlparse::{FormatOption, format};
+use sqlparse::{FormatOption, Formatter, MySqlDialect, format};
 
 #[test]
 fn test_strip_comments_single() {
@@ -628,6 +628,20 @@ fn test_reindent_join() {
 }
 
 
+#[test]
+fn test_reindent_window() {
+    let sql = "select a, row_number() over (partition by a, b order by c desc) as rn, sum(x) over w from t window w as (partition by a order by b rows between unbounded preceding and current row)";
+    let mut formatter = FormatOption::default_reindent();
+    let formatted_sql = format(sql, &mut formatter);
+    assert_eq!(formatted_sql, [
+        "select a,",
+        "       row_number() over (partition by a, b order by c desc) as rn,",
+        "       sum(x) over w",
+        "from t",
+        "window w as (partition by a order by b rows between unbounded preceding and current row)",
+    ].join("\n"));
+}
+
 #[test]
 fn test_format() {
     let sql = "select * from users limit 10";
@@ -639,4 +653,40 @@ fn test_format() {
     let sql = "select * from \"t\".\"users\" limit 10";
     let formatted_sql = format(sql, &mut formatter);
     assert_eq!(formatted_sql, "SELECT * FROM \"t\".\"users\" LIMIT 10");
-}
\ No newline at end of file
+}
+#[test]
+fn test_format_dollar_quoted_untouched() {
+    let sql = "create function f() returns void as $$\n  begin   perform  x ;\n  end;\n$$ language plpgsql;";
+    let mut formatter = FormatOption::default_reindent();
+    formatter.keyword_case = "upper";
+    formatter.identifier_case = "upper";
+    let formatted_sql = format(sql, &mut formatter);
+    assert_eq!(formatted_sql, "CREATE FUNCTION F() RETURNS VOID AS $$\n  begin   perform  x ;\n  end;\n$$ LANGUAGE PLPGSQL;");
+}
+
+#[test]
+fn test_format_mysql_delimiter() {
+    let sql = "delimiter $$\ncreate trigger t before insert on foo for each row begin set new.a = 1; end$$\ndelimiter ;\nselect 1;";
+    let mut formatter = Formatter::with_dialect(MySqlDialect);
+    let mut options = FormatOption::default_reindent();
+    options.keyword_case = "upper";
+    let formatted_sql = formatter.format(sql, &mut options);
+    assert_eq!(formatted_sql, [
+        "DELIMITER $$",
+        "CREATE TRIGGER t BEFORE",
+        "INSERT ON foo FOR EACH ROW BEGIN",
+        "SET new.a = 1; END$$",
+        "DELIMITER ;",
+        "SELECT 1;",
+    ].join("\n"));
+}
+
+#[test]
+fn test_format_prefixed_strings_untouched() {
+    let sql = "select n'abc', e'x\\ny', x'ff' from t";
+    let mut formatter = FormatOption::default();
+    formatter.keyword_case = "upper";
+    formatter.identifier_case = "upper";
+    let formatted_sql = format(sql, &mut formatter);
+    assert_eq!(formatted_sql, "SELECT n'abc', e'x\\ny', x'ff' FROM T");
+}
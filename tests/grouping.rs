@@ -1,4 +1,4 @@
-use sqlparse::{TokenType, group_tokenlist};
+use sqlparse::{TokenType, JoinKind, Span, group_tokenlist, parse};
 
 #[test]
 fn test_grouping_parenthesis() {
@@ -6,7 +6,7 @@ fn test_grouping_parenthesis() {
 
     let token_list = group_tokenlist(sql);
     assert_eq!(token_list.len(), 7);
-    assert_eq!(token_list.token_idx(Some(2)).unwrap().typ, TokenType::Parenthesis);
+    assert_eq!(token_list.token_idx(Some(2)).unwrap().typ, TokenType::Subquery);
     assert_eq!(token_list.token_idx(Some(6)).unwrap().typ, TokenType::Identifier);
     let sub_tokens = &token_list.token_idx(Some(2)).unwrap().children;
     assert_eq!(sub_tokens.token_idx(Some(3)).unwrap().typ, TokenType::Parenthesis);
@@ -49,11 +49,11 @@ fn test_compare_expr() {
     for (sql, a, b) in sqls {
         let token_list = group_tokenlist(sql);
         // println!("{}", token_list);
-        assert_eq!(token_list.len(), 9);
+        assert_eq!(token_list.len(), 7);
         assert_eq!(token_list.tokens[2].typ, TokenType::Identifier);
-        assert_eq!(token_list.tokens[6].typ, TokenType::Identifier);
-        assert_eq!(token_list.tokens[8].typ, TokenType::Where);
-        let where_token = &token_list.tokens[8].children;
+        assert_eq!(token_list.tokens[4].children.tokens[2].typ, TokenType::Identifier);
+        assert_eq!(token_list.tokens[6].typ, TokenType::Where);
+        let where_token = &token_list.tokens[6].children;
         assert_eq!(where_token.tokens[2].typ, TokenType::Comparison);
         assert_eq!(where_token.tokens.len(), 3);
         let comparison = &where_token.tokens[2].children;
@@ -76,8 +76,8 @@ fn test_grouping_identifiers() {
     let token_list = group_tokenlist(sql);
     // println!("{}", token_list);
     assert_eq!(token_list.tokens[2].typ, TokenType::Identifier);
-    assert_eq!(token_list.tokens[6].typ, TokenType::Identifier);
-    assert_eq!(token_list.tokens[8].typ, TokenType::Where);
+    assert_eq!(token_list.tokens[4].children.tokens[2].typ, TokenType::Identifier);
+    assert_eq!(token_list.tokens[6].typ, TokenType::Where);
     let sql = "select * from foo where foo.id = 1";
     let token_list = group_tokenlist(sql);
     let n = token_list.tokens.len();
@@ -86,18 +86,19 @@ fn test_grouping_identifiers() {
     assert_eq!(t.typ, TokenType::Identifier);
     let sql = r#"select * from (select "foo"."id" from foo)"#;
     let token_list = group_tokenlist(sql);
-    let c = &token_list.tokens[token_list.len()-1].children;
+    let from = &token_list.tokens[token_list.len()-1].children;
+    let c = &from.tokens[from.len()-1].children;
     assert_eq!(c.tokens[3].typ, TokenType::Identifier);
 
     let sql = "select 1.0*(a+b) as col, sum(c)/sum(d) from myschema.mytable";
     let token_list = group_tokenlist(sql);
-    assert_eq!(token_list.len(), 7);
+    assert_eq!(token_list.len(), 5);
     assert_eq!(token_list.tokens[2].typ, TokenType::IdentifierList);
     assert_eq!(token_list.tokens[2].children.len(), 4);
     let identifier_list = &token_list.tokens[2].children;
     let identifiers = identifier_list.get_identifiers();
     assert_eq!(identifiers.len(), 2);
-    assert_eq!(identifier_list.tokens[identifiers[0]].get_alias(), Some("col"));
+    assert_eq!(identifier_list.tokens[identifiers[0]].get_alias().as_deref(), Some("col"));
 }
 
 
@@ -168,7 +169,7 @@ fn test_grouping_identifier_invalid_in_middle() {
     assert_eq!(token_list.tokens[2].typ, TokenType::Identifier);
     assert_eq!(token_list.tokens[2].children.tokens[1].typ, TokenType::Punctuation);
     assert_eq!(token_list.tokens[3].typ, TokenType::Whitespace);
-    assert_eq!(token_list.tokens[2].value, "foo.");
+    assert_eq!(token_list.tokens[2].value(), "foo.");
 }
 
 
@@ -181,7 +182,7 @@ fn test_grouping_identifer_as() {
         assert_eq!(token_list.tokens[0].typ, TokenType::Identifier);
         let token = &token_list.tokens[0].children.tokens[2];
         assert_eq!(token.typ, TokenType::Keyword);
-        assert_eq!(token.normalized, "AS");
+        assert_eq!(token.normalized(), "AS");
     }
 }
 
@@ -232,7 +233,8 @@ fn test_grouping_identifier_list1() {
 fn test_grouping_identifier_list_subquery() {
     let sql = "select * from (select a, b + c as d from table) sub";
     let token_list = group_tokenlist(sql);
-    let subquery = &token_list.tokens[token_list.len()-1].children;
+    let from = &token_list.tokens[token_list.len()-1].children;
+    let subquery = &from.tokens[from.len()-1].children;
     let token_list = &subquery.tokens[0].children;
     let types = vec![TokenType::IdentifierList];
     let idx = token_list.token_next_by(&types, None, 0);
@@ -277,19 +279,20 @@ fn test_grouping_identifier_list_with_order() {
     assert_eq!(token_list.tokens[0].typ, TokenType::IdentifierList);
     let token = &token_list.tokens[0].children.tokens[3];
     assert_eq!(token.typ, TokenType::Identifier);
-    assert_eq!(token.value, "2 desc");
+    assert_eq!(token.value(), "2 desc");
 }
 
 #[test]
 fn test_grouping_where() {
     let sql = "select * from foo where bar = 1 order by id desc";
     let token_list = group_tokenlist(sql);
-    assert_eq!(token_list.len(), 12);
+    assert_eq!(token_list.len(), 8);
 
     let sql = "select x from (select y from foo where bar = 1) z";
     // let sql = "select y from foo where bar = 1";
     let token_list = group_tokenlist(sql);
-    let token_list = &token_list.tokens[token_list.len()-1].children.tokens[0].children;
+    let from = &token_list.tokens[token_list.len()-1].children;
+    let token_list = &from.tokens[from.len()-1].children.tokens[0].children;
     assert_eq!(token_list.tokens[token_list.len()-2].typ, TokenType::Where);
 }
 
@@ -297,10 +300,10 @@ fn test_grouping_where() {
 fn test_grouping_where_union() {
     let sql = "select 1 where 1 = 2 union select 2";
     let token_list = group_tokenlist(sql);
-    assert_eq!(token_list.tokens[5].value, "union");
+    assert_eq!(token_list.tokens[5].value(), "union");
     let sql = "select 1 where 1 = 2 union all select 2";
     let token_list = group_tokenlist(sql);
-    assert_eq!(token_list.tokens[5].value, "union all");
+    assert_eq!(token_list.tokens[5].value(), "union all");
 }
 
 
@@ -308,9 +311,9 @@ fn test_grouping_where_union() {
 fn test_returning_kw_ends_where_clause() {
     let sql = "delete from foo where x > y returning z";
     let token_list = group_tokenlist(sql);
-    assert_eq!(token_list.tokens[6].typ, TokenType::Where);
-    assert_eq!(token_list.tokens[7].typ, TokenType::Keyword);
-    assert_eq!(token_list.tokens[7].value, "returning");
+    assert_eq!(token_list.tokens[4].typ, TokenType::Where);
+    assert_eq!(token_list.tokens[5].typ, TokenType::Returning);
+    assert_eq!(token_list.tokens[5].children.tokens[0].value(), "returning");
 }
 
 
@@ -318,9 +321,9 @@ fn test_returning_kw_ends_where_clause() {
 fn test_into_kw_ends_where_clause() {
     let sql = "select * from foo where a = 1 into baz";
     let token_list = group_tokenlist(sql);
-    assert_eq!(token_list.tokens[8].typ, TokenType::Where);
-    assert_eq!(token_list.tokens[9].typ, TokenType::Keyword);
-    assert_eq!(token_list.tokens[9].value, "into");
+    assert_eq!(token_list.tokens[6].typ, TokenType::Where);
+    assert_eq!(token_list.tokens[7].typ, TokenType::Keyword);
+    assert_eq!(token_list.tokens[7].value(), "into");
 }
 
 #[test]
@@ -332,7 +335,7 @@ fn test_grouping_typecast() {
     for (sql, value) in sqls {
         let token_list = group_tokenlist(sql);
         let token_list = &token_list.tokens[2].children;
-        assert_eq!(token_list.tokens[token_list.len()-1].value, value);
+        assert_eq!(token_list.tokens[token_list.len()-1].value(), value);
     }
 }
 
@@ -342,30 +345,30 @@ fn test_grouping_alias() {
     let token_list = group_tokenlist(sql);
     let id = &token_list.tokens[2];
     let token_list = &token_list.tokens[2].children;
-    assert_eq!(token_list.tokens[0].value, "foo");
-    assert_eq!(token_list.tokens[token_list.len()-1].value, "bar");
+    assert_eq!(token_list.tokens[0].value(), "foo");
+    assert_eq!(token_list.tokens[token_list.len()-1].value(), "bar");
 
     assert_eq!(id.get_real_name().unwrap(), "foo");
     assert_eq!(id.get_alias().unwrap(), "bar");
 
     let sql = "select foo from mytable t1";
     let token_list = group_tokenlist(sql);
-    let id = &token_list.tokens[6];
-    let token_list = &token_list.tokens[6].children;
-    assert_eq!(token_list.tokens[0].value, "mytable");
-    assert_eq!(token_list.tokens[token_list.len()-1].value, "t1");
+    let id = &token_list.tokens[4].children.tokens[2];
+    let token_list = &id.children;
+    assert_eq!(token_list.tokens[0].value(), "mytable");
+    assert_eq!(token_list.tokens[token_list.len()-1].value(), "t1");
     assert_eq!(id.get_real_name().unwrap(), "mytable");
     assert_eq!(id.get_alias().unwrap(), "t1");
 
     let sql = "select foo::integer as bar from mytable";
     let token_list = group_tokenlist(sql);
     let token_list = &token_list.tokens[2].children;
-    assert_eq!(token_list.tokens[token_list.len()-1].value, "bar");
+    assert_eq!(token_list.tokens[token_list.len()-1].value(), "bar");
 
     let sql = "SELECT DISTINCT (current_database())::information_schema.sql_identifier AS view";
     let token_list = group_tokenlist(sql);
     let token_list = &token_list.tokens[4].children;
-    assert_eq!(token_list.tokens[token_list.len()-1].value, "view");
+    assert_eq!(token_list.tokens[token_list.len()-1].value(), "view");
 }
 
 #[test]
@@ -374,7 +377,7 @@ fn test_grouping_alias_case() {
     let token_list = group_tokenlist(sql);
     assert_eq!(token_list.len(), 1);
     let token_list = &token_list.tokens[0].children;
-    assert_eq!(token_list.tokens[token_list.len()-1].value, "foo");
+    assert_eq!(token_list.tokens[token_list.len()-1].value(), "foo");
 }
 
 #[test]
@@ -425,6 +428,25 @@ fn test_grouping_function() {
     assert_eq!(token_list.token_idx(Some(0)).unwrap().typ, TokenType::Function);
 }
 
+#[test]
+fn test_grouping_nested_function() {
+    // functions inside parenthesis and operations are grouped too
+    let sql = "(f(a) + 1) * 2";
+    let token_list = group_tokenlist(sql);
+    let operation = &token_list.tokens[0].children.tokens[0].children.tokens[1];
+    assert_eq!(operation.typ, TokenType::Operation);
+    assert_eq!(operation.children.tokens[0].typ, TokenType::Function);
+    assert_eq!(operation.children.tokens[0].value(), "f(a)");
+
+    let sql = "x in (g(b), h(c))";
+    let token_list = group_tokenlist(sql);
+    let parenthesis = token_list.tokens[0].children.tokens.last().unwrap();
+    let items = &parenthesis.children.tokens[1];
+    assert_eq!(items.typ, TokenType::IdentifierList);
+    let functions = items.children.tokens.iter().filter(|t| t.typ == TokenType::Function).map(|t| t.value()).collect::<Vec<_>>();
+    assert_eq!(functions, vec!["g(b)", "h(c)"]);
+}
+
 #[test]
 fn test_grouping_function_not_in() {
     let sql = "in(1, 2)";
@@ -441,8 +463,8 @@ fn test_grouping_in_comparison() {
     assert_eq!(token_list.len(), 1);
     let token_list = &token_list.tokens[0].children;
     assert_eq!(token_list.len(), 5);
-    assert_eq!(token_list.tokens[0].value, "a");
-    assert_eq!(token_list.tokens[token_list.len()-1].value, "(1, 2)");
+    assert_eq!(token_list.tokens[0].value(), "a");
+    assert_eq!(token_list.tokens[token_list.len()-1].value(), "(1, 2)");
 }
 
 
@@ -487,12 +509,14 @@ fn test_grouping_identifier_with_string_literals() {
 fn test_grouping_identifier_consumes_ordering() {
     let sql = "select * from foo order by c1 desc, c2, c3";
     let token_list = group_tokenlist(sql);
-    assert_eq!(token_list.tokens[token_list.len()-1].typ, TokenType::IdentifierList);
-    let token_list = &token_list.tokens[token_list.len()-1].children;
+    let order_by = &token_list.tokens[token_list.len()-1];
+    assert_eq!(order_by.typ, TokenType::OrderBy);
+    assert_eq!(order_by.children.tokens[2].typ, TokenType::IdentifierList);
+    let token_list = &order_by.children.tokens[2].children;
     let ids = token_list.get_identifiers();
     assert_eq!(ids.len(), 3);
-    assert_eq!(token_list.tokens[ids[0]].value, "c1 desc");
-    assert_eq!(token_list.tokens[ids[1]].value, "c2");
+    assert_eq!(token_list.tokens[ids[0]].value(), "c1 desc");
+    assert_eq!(token_list.tokens[ids[1]].value(), "c2");
     assert_eq!(token_list.tokens[ids[0]].get_name().unwrap(), "c1");
     assert_eq!(token_list.tokens[ids[1]].get_name().unwrap(), "c2");
 }
@@ -518,8 +542,8 @@ fn test_grouping_comparison_with_floats() {
     assert_eq!(token_list.token_idx(Some(0)).unwrap().typ, TokenType::Comparison);
     assert_eq!(token_list.tokens[0].children.len(), 5);
     let token_list = &token_list.tokens[0].children;
-    assert_eq!(token_list.tokens[0].value, "foo");
-    assert_eq!(token_list.tokens[token_list.len()-1].value, "25.5");
+    assert_eq!(token_list.tokens[0].value(), "foo");
+    assert_eq!(token_list.tokens[token_list.len()-1].value(), "25.5");
 }
 
 
@@ -543,7 +567,7 @@ fn test_grouping_comparison_with_strings() {
         assert_eq!(token_list.len(), 1);
         assert_eq!(token_list.token_idx(Some(0)).unwrap().typ, TokenType::Comparison);
         let token_list = &token_list.tokens[0].children;
-        assert_eq!(token_list.tokens[token_list.len()-1].value, "bar");
+        assert_eq!(token_list.tokens[token_list.len()-1].value(), "bar");
     }
 }
 
@@ -558,8 +582,8 @@ fn test_grouping_comparison_with_functions() {
     assert_eq!(token_list.tokens[0].typ, TokenType::Comparison);
     let token_list = &token_list.tokens[0].children;
     assert_eq!(token_list.len(), 5);
-    assert_eq!(token_list.tokens[0].value, "foo");
-    assert_eq!(token_list.tokens[token_list.len()-1].value, "DATE(bar.baz)");
+    assert_eq!(token_list.tokens[0].value(), "foo");
+    assert_eq!(token_list.tokens[token_list.len()-1].value(), "DATE(bar.baz)");
 
     let sql = "DATE(foo.bar) = DATE(bar.baz)";
     let token_list = group_tokenlist(sql);
@@ -567,8 +591,8 @@ fn test_grouping_comparison_with_functions() {
     assert_eq!(token_list.tokens[0].typ, TokenType::Comparison);
     let token_list = &token_list.tokens[0].children;
     assert_eq!(token_list.len(), 5);
-    assert_eq!(token_list.tokens[0].value, "DATE(foo.bar)");
-    assert_eq!(token_list.tokens[token_list.len()-1].value, "DATE(bar.baz)");
+    assert_eq!(token_list.tokens[0].value(), "DATE(foo.bar)");
+    assert_eq!(token_list.tokens[token_list.len()-1].value(), "DATE(bar.baz)");
 
     let sql = "DATE(foo.bar) = bar.baz";
     let token_list = group_tokenlist(sql);
@@ -576,8 +600,8 @@ fn test_grouping_comparison_with_functions() {
     assert_eq!(token_list.tokens[0].typ, TokenType::Comparison);
     let token_list = &token_list.tokens[0].children;
     assert_eq!(token_list.len(), 5);
-    assert_eq!(token_list.tokens[0].value, "DATE(foo.bar)");
-    assert_eq!(token_list.tokens[token_list.len()-1].value, "bar.baz");
+    assert_eq!(token_list.tokens[0].value(), "DATE(foo.bar)");
+    assert_eq!(token_list.tokens[token_list.len()-1].value(), "bar.baz");
 }
 
 #[test]
@@ -588,9 +612,9 @@ fn test_grouping_comparison_with_typed_literal() {
     assert_eq!(token_list.tokens[0].typ, TokenType::Comparison);
     let token_list = &token_list.tokens[0].children;
     assert_eq!(token_list.len(), 5);
-    assert_eq!(token_list.tokens[0].value, "foo");
+    assert_eq!(token_list.tokens[0].value(), "foo");
     assert_eq!(token_list.tokens[token_list.len()-1].typ, TokenType::TypedLiteral);
-    assert_eq!(token_list.tokens[token_list.len()-1].value, "DATE 'bar.baz'");
+    assert_eq!(token_list.tokens[token_list.len()-1].value(), "DATE 'bar.baz'");
 }
 
 #[test]
@@ -609,12 +633,12 @@ fn test_grouping_nested_for() {
     let token_list = group_tokenlist(sql);
     assert_eq!(token_list.len(), 1);
     let children_len = token_list.tokens[0].children.len();
-    assert_eq!(token_list.tokens[0].children.token_idx(Some(0)).unwrap().normalized, "FOR");
-    assert_eq!(token_list.tokens[0].children.token_idx(Some(children_len-1)).unwrap().normalized, "END LOOP");
+    assert_eq!(token_list.tokens[0].children.token_idx(Some(0)).unwrap().normalized(), "FOR");
+    assert_eq!(token_list.tokens[0].children.token_idx(Some(children_len-1)).unwrap().normalized(), "END LOOP");
     let inner = token_list.tokens[0].children.token_idx(Some(6)).unwrap();
-    assert_eq!(inner.children.token_idx(Some(0)).unwrap().normalized, "FOR");
+    assert_eq!(inner.children.token_idx(Some(0)).unwrap().normalized(), "FOR");
     let inner_len = inner.children.len();
-    assert_eq!(inner.children.token_idx(Some(inner_len-1)).unwrap().normalized, "END LOOP");
+    assert_eq!(inner.children.token_idx(Some(inner_len-1)).unwrap().normalized(), "END LOOP");
 }
 
 #[test]
@@ -640,12 +664,12 @@ fn test_grouping_nested_begin() {
     let token_list = group_tokenlist(sql);
     assert_eq!(token_list.len(), 1);
     let children_len = token_list.tokens[0].children.len();
-    assert_eq!(token_list.tokens[0].children.token_idx(Some(0)).unwrap().normalized, "BEGIN");
-    assert_eq!(token_list.tokens[0].children.token_idx(Some(children_len-1)).unwrap().normalized, "END");
+    assert_eq!(token_list.tokens[0].children.token_idx(Some(0)).unwrap().normalized(), "BEGIN");
+    assert_eq!(token_list.tokens[0].children.token_idx(Some(children_len-1)).unwrap().normalized(), "END");
     let inner = token_list.tokens[0].children.token_idx(Some(4)).unwrap();
-    assert_eq!(inner.children.token_idx(Some(0)).unwrap().normalized, "BEGIN");
+    assert_eq!(inner.children.token_idx(Some(0)).unwrap().normalized(), "BEGIN");
     let inner_len = inner.children.len();
-    assert_eq!(inner.children.token_idx(Some(inner_len-1)).unwrap().normalized, "END");
+    assert_eq!(inner.children.token_idx(Some(inner_len-1)).unwrap().normalized(), "END");
 }
 
 #[test]
@@ -655,8 +679,8 @@ fn test_grouping_aliased_column_without_as() {
     assert_eq!(token_list.len(), 1);
     let id = &token_list.tokens[0];
     let token_list = &token_list.tokens[0].children;
-    assert_eq!(token_list.tokens[0].value, "foo");
-    assert_eq!(token_list.tokens.last().unwrap().value, "bar");
+    assert_eq!(token_list.tokens[0].value(), "foo");
+    assert_eq!(token_list.tokens.last().unwrap().value(), "bar");
     assert_eq!(id.get_real_name().unwrap(), "foo");
     assert_eq!(id.get_alias().unwrap(), "bar");
 
@@ -664,7 +688,7 @@ fn test_grouping_aliased_column_without_as() {
     let token_list = group_tokenlist(sql);
     let id = &token_list.tokens[0];
     let token_list = &token_list.tokens[0].children;
-    assert_eq!(token_list.tokens.last().unwrap().value, "baz");
+    assert_eq!(token_list.tokens.last().unwrap().value(), "baz");
     assert_eq!(id.get_parent_name().unwrap(), "foo");
     assert_eq!(id.get_real_name().unwrap(), "bar");
     assert_eq!(id.get_alias().unwrap(), "baz");
@@ -676,7 +700,7 @@ fn test_grouping_qualified_function() {
     let token_list = group_tokenlist(sql);
     let id = &token_list.tokens[0];
     let token_list = &token_list.tokens[0].children;
-    assert_eq!(token_list.tokens[0].value, "foo");
+    assert_eq!(token_list.tokens[0].value(), "foo");
     assert_eq!(id.get_parent_name(), None);
     assert_eq!(id.get_real_name().unwrap(), "foo");
 
@@ -694,7 +718,7 @@ fn test_grouping_aliased_function_without_as() {
     assert_eq!(token_list.len(), 1);
     let id = &token_list.tokens[0];
     let token_list = &token_list.tokens[0].children;
-    assert_eq!(token_list.tokens.last().unwrap().value, "bar");
+    assert_eq!(token_list.tokens.last().unwrap().value(), "bar");
     assert_eq!(id.get_real_name().unwrap(), "foo");
     assert_eq!(id.get_alias().unwrap(), "bar");
 
@@ -703,7 +727,7 @@ fn test_grouping_aliased_function_without_as() {
     assert_eq!(token_list.len(), 1);
     let id = &token_list.tokens[0];
     let token_list = &token_list.tokens[0].children;
-    assert_eq!(token_list.tokens.last().unwrap().value, "baz");
+    assert_eq!(token_list.tokens.last().unwrap().value(), "baz");
     assert_eq!(id.get_parent_name().unwrap(), "foo");
     assert_eq!(id.get_real_name().unwrap(), "bar");
     assert_eq!(id.get_alias().unwrap(), "baz");
@@ -716,7 +740,7 @@ fn test_grouping_aliased_literal_without_as() {
     assert_eq!(token_list.len(), 1);
     let id = &token_list.tokens[0];
     let token_list = &token_list.tokens[0].children;
-    assert_eq!(token_list.tokens.last().unwrap().value, "foo");
+    assert_eq!(token_list.tokens.last().unwrap().value(), "foo");
     assert_eq!(id.get_alias().unwrap(), "foo");
 }
 
@@ -726,6 +750,211 @@ fn test_grouping_as_cte() {
     let token_list = group_tokenlist(sql);
     let token_list = &token_list.tokens[0].children.tokens[0].children;
     assert!(token_list.len() > 4);
-    assert_eq!(token_list.tokens[2].value, "AS");
-    assert_eq!(token_list.tokens[4].value, "WITH");
-}
\ No newline at end of file
+    assert_eq!(token_list.tokens[2].value(), "AS");
+    assert_eq!(token_list.tokens[4].value(), "WITH");
+}
+#[test]
+fn test_grouping_span() {
+    let sql = "select a\nfrom foo.bar as b\nwhere x = 1";
+    let token_list = group_tokenlist(sql);
+    let from = &token_list.tokens[4];
+    assert_eq!(from.span, Span::new(9, 26, 2, 1));
+    let id = &from.children.tokens[2];
+    assert_eq!(id.typ, TokenType::Identifier);
+    assert_eq!(id.span, Span::new(14, 26, 2, 6));
+    assert_eq!(&sql[id.span.start..id.span.end], "foo.bar as b");
+    let where_token = token_list.tokens.last().unwrap();
+    assert_eq!(where_token.typ, TokenType::Where);
+    assert_eq!(where_token.span.line, 3);
+    assert_eq!(where_token.span.column, 1);
+    assert_eq!(where_token.span.end, sql.len());
+}
+
+#[test]
+fn test_grouping_update_value() {
+    let sql = "select a, b from foo";
+    let mut tokens = parse(sql);
+    assert_eq!(tokens[2].value(), "a, b");
+    tokens[2].children.tokens[0].children.tokens[0].set_value("x");
+    tokens[2].children.tokens[0].update_value();
+    tokens[2].update_value();
+    assert_eq!(tokens[2].value(), "x, b");
+}
+
+#[test]
+fn test_grouping_cte() {
+    let sql = "with recursive a as (select 1), b (x, y) as not materialized (select 2, 3) select * from a";
+    let token_list = group_tokenlist(sql);
+    let with = &token_list.tokens[0];
+    assert_eq!(with.typ, TokenType::With);
+    assert_eq!(with.value(), "with recursive a as (select 1), b (x, y) as not materialized (select 2, 3)");
+    assert!(with.is_recursive());
+    assert_eq!(token_list.tokens[2].value(), "select");
+    let ctes = with.get_ctes();
+    assert_eq!(ctes.len(), 2);
+    assert_eq!(ctes[0].typ, TokenType::CommonTableExpression);
+    assert_eq!(ctes[0].get_real_name().as_deref(), Some("a"));
+    assert!(ctes[0].get_cte_columns().is_empty());
+    assert_eq!(ctes[0].get_cte_body().unwrap().value(), "(select 1)");
+    assert_eq!(ctes[0].get_materialized(), None);
+    assert_eq!(ctes[1].get_real_name().as_deref(), Some("b"));
+    assert_eq!(ctes[1].get_cte_columns(), vec!["x", "y"]);
+    assert_eq!(ctes[1].get_cte_body().unwrap().value(), "(select 2, 3)");
+    assert_eq!(ctes[1].get_materialized(), Some(false));
+
+    let sql = "insert into t with c as materialized (select 1) select * from c";
+    let token_list = group_tokenlist(sql);
+    let with = token_list.tokens.iter().find(|t| t.typ == TokenType::With).unwrap();
+    assert!(!with.is_recursive());
+    assert_eq!(with.get_ctes()[0].get_materialized(), Some(true));
+
+    let sql = "select * from (with c as (select 1) select * from c) q";
+    let token_list = group_tokenlist(sql);
+    let parenthesis = &token_list.tokens[4].children.tokens[2].children.tokens[0];
+    assert_eq!(parenthesis.children.tokens[1].typ, TokenType::With);
+}
+
+#[test]
+fn test_grouping_subquery() {
+    let sql = "select (a + b), (select 1) from t where x in (select y from (select y from u) v) and z in (1, 2)";
+    let token_list = group_tokenlist(sql);
+    let subqueries = token_list.subqueries().map(|(depth, t)| (depth, t.value())).collect::<Vec<_>>();
+    assert_eq!(subqueries, vec![
+        (1, "(select 1)"),
+        (1, "(select y from (select y from u) v)"),
+        (2, "(select y from u)"),
+    ]);
+    assert!(token_list.subqueries().all(|(_, t)| t.typ == TokenType::Subquery));
+
+    let sql = "with c as (select 1) select * from c where x = (/* max */ select max(y) from (with d as (select 2) select * from d) e)";
+    let token_list = group_tokenlist(sql);
+    let subqueries = token_list.subqueries().map(|(depth, t)| (depth, t.value())).collect::<Vec<_>>();
+    assert_eq!(subqueries, vec![
+        (1, "(select 1)"),
+        (1, "(/* max */ select max(y) from (with d as (select 2) select * from d) e)"),
+        (2, "(with d as (select 2) select * from d)"),
+        (3, "(select 2)"),
+    ]);
+
+    let token_list = group_tokenlist("select (a + b) * 2, f(x), ((select 1))");
+    assert_eq!(token_list.subqueries().map(|(depth, t)| (depth, t.value())).collect::<Vec<_>>(), vec![(1, "(select 1)")]);
+}
+
+#[test]
+fn test_grouping_clauses() {
+    let sql = "select a, b from t1 x left join t2 y on x.id = y.id where x.a > 1 group by a, b having count(*) > 1 window w as (order by a) order by a desc limit 10 offset 5;";
+    let token_list = group_tokenlist(sql);
+    let clauses = token_list.tokens.iter().filter(|t| t.typ.is_clause() || t.typ == TokenType::Where).map(|t| (t.typ.clone(), t.value())).collect::<Vec<_>>();
+    assert_eq!(clauses, vec![
+        (TokenType::From, "from t1 x"),
+        (TokenType::Join, "left join t2 y on x.id = y.id"),
+        (TokenType::Where, "where x.a > 1 "),
+        (TokenType::GroupBy, "group by a, b"),
+        (TokenType::Having, "having count(*) > 1"),
+        (TokenType::Window, "window w as (order by a)"),
+        (TokenType::OrderBy, "order by a desc"),
+        (TokenType::Limit, "limit 10"),
+        (TokenType::Offset, "offset 5"),
+    ]);
+    assert_eq!(token_list.tokens.last().unwrap().value(), ";");
+
+    let token_list = group_tokenlist("select a from t order by a offset 2 rows fetch first 10 rows only");
+    assert_eq!(token_list.tokens[8].typ, TokenType::Offset);
+    assert_eq!(token_list.tokens[10].typ, TokenType::Fetch);
+    assert_eq!(token_list.tokens[10].value(), "fetch first 10 rows only");
+
+    let token_list = group_tokenlist("delete from t where a = 1 returning *");
+    assert_eq!(token_list.tokens[2].typ, TokenType::From);
+    assert_eq!(token_list.tokens[5].typ, TokenType::Returning);
+
+    // clauses of a subquery end at its parenthesis
+    let token_list = group_tokenlist("select * from (select a from t order by a) q union select 1");
+    let from = &token_list.tokens[4];
+    assert_eq!(from.typ, TokenType::From);
+    assert_eq!(from.value(), "from (select a from t order by a) q");
+    let subquery = &from.children.tokens[2].children.tokens[0];
+    assert_eq!(subquery.children.tokens[7].typ, TokenType::OrderBy);
+    assert_eq!(subquery.children.tokens[7].value(), "order by a");
+
+    // FROM is only a clause after a DML keyword
+    let token_list = group_tokenlist("select a is distinct from b, extract(year from d) from t");
+    assert_eq!(token_list.tokens.iter().filter(|t| t.typ == TokenType::From).count(), 1);
+    assert!(group_tokenlist("from t").tokens.iter().all(|t| !t.typ.is_clause()));
+}
+
+#[test]
+fn test_grouping_nested_order() {
+    // ASC and DESC are grouped with their identifier at every level
+    let token_list = group_tokenlist("select (a desc)");
+    let parenthesis = &token_list.tokens[2];
+    assert_eq!(parenthesis.children.tokens[1].typ, TokenType::Identifier);
+    assert_eq!(parenthesis.children.tokens[1].value(), "a desc");
+
+    let token_list = group_tokenlist("row_number() over (order by c desc)");
+    let spec = token_list.tokens[0].get_window().unwrap().get_window_spec().unwrap();
+    assert_eq!(spec.children.tokens[3].typ, TokenType::Identifier);
+    assert_eq!(spec.children.tokens[3].value(), "c desc");
+}
+
+#[test]
+fn test_grouping_window() {
+    let sql = "select row_number() over (partition by a, b order by c desc, d rows between unbounded preceding and current row) as rn, sum(x) over w from t window w as (partition by a), w2 as (order by b)";
+    let token_list = group_tokenlist(sql);
+    let list = &token_list.tokens[2];
+    assert_eq!(list.typ, TokenType::IdentifierList);
+    let function = &list.children.tokens[0].children.tokens[0];
+    assert_eq!(function.typ, TokenType::Function);
+    let over = function.get_window().unwrap();
+    assert_eq!(over.typ, TokenType::Over);
+    assert!(over.get_window_name().is_none());
+    let spec = over.get_window_spec().unwrap();
+    assert_eq!(spec.typ, TokenType::WindowSpec);
+    let values = |tokens: Vec<&sqlparse::Token>| tokens.iter().map(|t| t.value().to_string()).collect::<Vec<_>>();
+    assert_eq!(values(spec.get_partition_keys()), vec!["a", "b"]);
+    assert_eq!(values(spec.get_order_keys()), vec!["c desc", "d"]);
+    let frame = spec.get_window_frame().unwrap();
+    assert_eq!(frame.iter().map(|t| t.value()).collect::<String>(), "rows between unbounded preceding and current row");
+    assert!(frame.iter().filter(|t| !t.is_whitespace()).all(|t| t.is_keyword()));
+
+    let function = &list.children.tokens[3];
+    assert_eq!(function.value(), "sum(x) over w");
+    let over = function.get_window().unwrap();
+    assert_eq!(over.get_window_name(), Some("w"));
+    assert!(over.get_window_spec().is_none());
+
+    let window = token_list.tokens.iter().find(|t| t.typ == TokenType::Window).unwrap();
+    let windows = window.get_named_windows();
+    assert_eq!(windows.iter().map(|(name, _)| name.as_ref()).collect::<Vec<_>>(), vec!["w", "w2"]);
+    assert_eq!(values(windows[0].1.get_partition_keys()), vec!["a"]);
+    assert!(windows[0].1.get_order_keys().is_empty());
+    assert_eq!(values(windows[1].1.get_order_keys()), vec!["b"]);
+    assert!(windows[1].1.get_window_frame().is_none());
+
+    // OVER only belongs to a function
+    let token_list = group_tokenlist("select count(*) from t");
+    assert!(token_list.tokens[2].get_window().is_none());
+}
+
+#[test]
+fn test_grouping_join() {
+    let sql = "select * from a join b on a.x = b.x and a.y = b.y left outer join c using (id, \"name\") cross join d natural join e join (select 1) s on true straight_join f";
+    let token_list = group_tokenlist(sql);
+    let joins = token_list.tokens.iter().filter(|t| t.typ == TokenType::Join).collect::<Vec<_>>();
+    let kinds = joins.iter().map(|t| t.join_kind().unwrap()).collect::<Vec<_>>();
+    assert_eq!(kinds, vec![JoinKind::Inner, JoinKind::Left, JoinKind::Cross, JoinKind::Natural, JoinKind::Inner, JoinKind::Straight]);
+    let targets = joins.iter().map(|t| t.join_target().unwrap().get_real_name().unwrap()).collect::<Vec<_>>();
+    assert_eq!(targets, vec!["b", "c", "d", "e", "s", "f"]);
+
+    let condition = joins[0].join_condition().unwrap();
+    assert_eq!(condition[0].typ, TokenType::Comparison);
+    assert_eq!(condition.iter().map(|t| t.value()).collect::<String>(), "a.x = b.x and a.y = b.y");
+    assert!(joins[0].join_using().is_empty());
+    assert!(joins[1].join_condition().is_none());
+    assert_eq!(joins[1].join_using(), vec!["id", "name"]);
+    // joins without a condition
+    assert!(joins[2..4].iter().chain(&joins[5..]).all(|t| t.join_condition().is_none() && t.join_using().is_empty()));
+    assert_eq!(joins[4].join_target().unwrap().children.tokens[0].typ, TokenType::Subquery);
+
+    assert_eq!(token_list.tokens[0].join_kind(), None);
+    assert!(token_list.tokens[0].join_target().is_none());
+}
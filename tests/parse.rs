@@ -23,7 +23,7 @@ fn test_parse_placeholder() {
         let token_list = group_tokenlist(sql);
         let token_list = &token_list.tokens[token_list.len()-1].children;
         assert_eq!(token_list.tokens.last().unwrap().typ, TokenType::NamePlaceholder);
-        assert_eq!(token_list.tokens.last().unwrap().value, placeholder);
+        assert_eq!(token_list.tokens.last().unwrap().value(), placeholder);
     }
 }
 
@@ -43,9 +43,9 @@ fn test_parse_access_symbol() {
     let token_list = group_tokenlist(sql);
     assert_eq!(token_list.tokens.last().unwrap().typ, TokenType::Identifier);
     let token_list = &token_list.tokens.last().unwrap().children;
-    assert_eq!(token_list.tokens[token_list.len()-1].value, "foo");
-    assert_eq!(token_list.tokens[0].value, "a");
-    assert_eq!(token_list.tokens[2].value, "[foo bar]");
+    assert_eq!(token_list.tokens[token_list.len()-1].value(), "foo");
+    assert_eq!(token_list.tokens[0].value(), "a");
+    assert_eq!(token_list.tokens[2].value(), "[foo bar]");
 }
 
 #[test]
@@ -53,8 +53,8 @@ fn test_parse_square_brackets_notation_isnt_too_greedy() {
     let sql = "[foo], [bar]";
     let token_list = group_tokenlist(sql);
     assert_eq!(token_list.len(), 4);
-    assert_eq!(token_list.tokens[0].value, "[foo]");
-    assert_eq!(token_list.tokens[token_list.len()-1].value, "[bar]");
+    assert_eq!(token_list.tokens[0].value(), "[foo]");
+    assert_eq!(token_list.tokens[token_list.len()-1].value(), "[bar]");
 }
 
 #[test]
@@ -119,7 +119,7 @@ fn test_parse_double_precision_is_builtin() {
     let sql = "DOUBLE PRECISION";
     let token_list = group_tokenlist(sql);
     assert_eq!(token_list.len(), 1);
-    assert_eq!(token_list.tokens[0].value, "DOUBLE PRECISION");
+    assert_eq!(token_list.tokens[0].value(), "DOUBLE PRECISION");
     assert_eq!(token_list.tokens[0].typ, TokenType::NameBuiltin);
 }
 
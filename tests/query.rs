@@ -0,0 +1,47 @@
+use sqlparse::{Parser, Query, SelectorError, TokenList, TokenType};
+
+fn values(tokens: &TokenList, selector: &str) -> Vec<String> {
+    tokens.select(selector).unwrap().iter().map(|m| m.token.value().to_string()).collect()
+}
+
+#[test]
+fn test_query_select() {
+    let stmts = Parser::default().parse_statements("select count(a), f(b) from t join u on t.id = u.id where a = 1 and b in (select max(c) from v)");
+    let tokens = &stmts[0].tokens;
+    assert_eq!(values(tokens, "Where > Comparison"), ["a = 1", "b in (select max(c) from v)"]);
+    assert_eq!(values(tokens, "Where Function"), ["max(c)"]);
+    assert_eq!(values(tokens, "Function"), ["count(a)", "f(b)", "max(c)"]);
+    assert_eq!(values(tokens, "Function[name=COUNT]"), ["count(a)"]);
+    assert_eq!(values(tokens, "> From > Identifier"), ["t"]);
+    assert_eq!(values(tokens, "Subquery From>*[value='v']"), ["v"]);
+    assert_eq!(values(tokens, ">Where Subquery > KeywordDML"), ["select"]);
+    assert_eq!(values(tokens, "Join Comparison"), ["t.id = u.id"]);
+    assert!(values(tokens, "> Function").is_empty());
+
+    let q = Query::descendant(TokenType::Where).then_descendant(TokenType::Function).with_name("max");
+    let matches = tokens.query(&q);
+    assert_eq!(matches.len(), 1);
+    assert_eq!(tokens.token_at(&matches[0].path), Some(matches[0].token));
+    assert_eq!(q, "Where Function[name=max]".parse().unwrap());
+    assert_eq!(Query::child(TokenType::Where), Query::parse(" > Where ").unwrap());
+}
+
+#[test]
+fn test_query_errors() {
+    let error = |selector: &str| Query::parse(selector).unwrap_err();
+    assert_eq!(error(""), SelectorError { message: "empty selector".to_string(), pos: 0 });
+    assert_eq!(error("Where > Nope"), SelectorError { message: "unknown token type Nope".to_string(), pos: 8 });
+    assert_eq!(error("Where >").pos, 7);
+    assert_eq!(error("Function[name]").message, "expected '='");
+    assert_eq!(error("Function[size=1]").message, "unknown attribute size");
+    assert_eq!(error("Function[name='a]").message, "unterminated string");
+    assert_eq!(error("Where,Function").message, "expected whitespace or '>'");
+}
+
+#[test]
+fn test_token_type_from_name() {
+    for typ in [TokenType::Text, TokenType::KeywordDML, TokenType::Where, TokenType::WindowSpec, TokenType::CommonTableExpression] {
+        assert_eq!(TokenType::from_name(&format!("{:?}", typ)), Some(typ));
+    }
+    assert_eq!(TokenType::from_name("where"), None);
+}
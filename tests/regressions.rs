@@ -63,12 +63,12 @@ fn test_reg38() {
 fn test_reg39() {
     let sql = "select user.id from user";
     let token_list = group_tokenlist(sql);
-    assert_eq!(token_list.len(), 7);
+    assert_eq!(token_list.len(), 5);
     assert_eq!(token_list.tokens[2].typ, TokenType::Identifier);
     let token_list = &token_list.tokens[2].children;
-    assert_eq!(token_list.tokens[0].value, "user");
+    assert_eq!(token_list.tokens[0].value(), "user");
     assert_eq!(token_list.tokens[1].typ, TokenType::Punctuation);
-    assert_eq!(token_list.tokens[2].value, "id");
+    assert_eq!(token_list.tokens[2].value(), "id");
 }
 
 
@@ -77,8 +77,10 @@ fn test_reg40() {
     let sql = "SELECT id, name FROM (SELECT id, name FROM bar) as foo";
     let token_list = group_tokenlist(sql);
     // println!("{}", token_list);
-    assert_eq!(token_list.len(), 7);
+    assert_eq!(token_list.len(), 5);
     assert_eq!(token_list.tokens[2].typ, TokenType::IdentifierList);
+    assert_eq!(token_list.tokens.last().unwrap().typ, TokenType::From);
+    let token_list = &token_list.tokens.last().unwrap().children;
     assert_eq!(token_list.tokens.last().unwrap().typ, TokenType::Identifier);
     let token_list = &token_list.tokens.last().unwrap().children;
     let token_list = &token_list.tokens[0].children;
@@ -123,7 +125,7 @@ fn test_reg78() {
         let token_list = group_tokenlist(sql);
         assert_eq!(token_list.tokens[2].typ, TokenType::Identifier);
         let token_list = &token_list.tokens[2].children;
-        assert_eq!(token_list.tokens.last().unwrap().value,  name);
+        assert_eq!(token_list.tokens.last().unwrap().value(),  name);
     }
 }
 
@@ -219,9 +221,9 @@ fn test_reg322_concurrently_is_keyword() {
     assert_eq!(token_list.tokens[0].typ, TokenType::KeywordDDL);
     assert_eq!(token_list.tokens[2].typ, TokenType::Keyword);
     assert_eq!(token_list.tokens[4].typ, TokenType::Keyword);
-    assert_eq!(token_list.tokens[4].value, "CONCURRENTLY");
+    assert_eq!(token_list.tokens[4].value(), "CONCURRENTLY");
     assert_eq!(token_list.tokens[6].typ, TokenType::Identifier);
-    assert_eq!(token_list.tokens[6].value, "myindex");
+    assert_eq!(token_list.tokens[6].value(), "myindex");
 }
 
 // FIXME: test_reg484_comments_and_newlines
@@ -235,10 +237,12 @@ fn test_reg484_comments_and_newlines() {
     let mut formatter = FormatOption::default();
     formatter.strip_comments = true;
     let formatted_sql = format(sql, &mut formatter);
+    // the space before the newline left by the comment is stripped inside the group too,
+    // the text of the group is joined again after its children change
     assert_eq!(formatted_sql, vec![
         "Create table myTable",
         "(",
-        "    myId TINYINT NOT NULL, ",
+        "    myId TINYINT NOT NULL,",
         "myName VARCHAR2(100) NOT NULL",
         ")"
         ].join("\n"))
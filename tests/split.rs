@@ -1,8 +1,8 @@
-
-use sqlparse::{Token, parse_multi};
+use std::io::BufReader;
+use sqlparse::{Token, Parser, Dialect, GenericDialect, PostgreSqlDialect, MsSqlDialect, MySqlDialect, OracleDialect, BigQueryDialect, parse_multi, split, StatementSlice};
 
 fn to_string(tokens: &[Token]) -> String {
-    tokens.iter().map(|t| t.value.as_str()).collect::<Vec<_>>().join("")
+    tokens.iter().map(|t| t.value()).collect::<Vec<_>>().join("")
 }
 
 
@@ -60,8 +60,8 @@ LANGUAGE 'plpgsql' VOLATILE CALLED ON NULL INPUT SECURITY INVOKER;
 "#;
     let stmts = parse_multi(sql);
     assert_eq!(stmts.len(), 1);
-    // let sql1 = to_string(&stmts[0]);
-    // assert_eq!(sql, sql1);
+    let sql1 = to_string(&stmts[0]);
+    assert_eq!(sql.trim_end(), sql1);
 }
 
 #[test]
@@ -149,3 +149,260 @@ fn test_split_comment_end_of_line() {
     assert_eq!(sql1[0], "select * from foo; -- foo\n");
 }
 
+
+#[test]
+fn test_split_dollar_quoted_function() {
+    let sql = r#"CREATE FUNCTION inc(i integer) RETURNS integer AS $$
+BEGIN
+    RETURN i + 1;
+END;
+$$ LANGUAGE plpgsql;
+CREATE FUNCTION dec(i integer) RETURNS integer AS $fn$ BEGIN RETURN i - 1; END; $fn$ LANGUAGE plpgsql;
+select 1;"#;
+    let stmts = parse_multi(sql);
+    assert_eq!(stmts.len(), 3);
+    assert!(to_string(&stmts[1]).trim_start().starts_with("CREATE FUNCTION dec"));
+}
+
+#[test]
+fn test_split_tsql_go() {
+    let sql = "select 1\nGO\nselect * from [dbo].[go]\n  go  -- end of batch\nselect 2;\nselect 'go';\nselect go\ngo";
+    let p = Parser::with_dialect(MsSqlDialect);
+    let stmts = p.parse_multi(sql);
+    let stmts = stmts.iter().map(|s| to_string(s)).collect::<Vec<_>>();
+    assert_eq!(stmts, vec![
+        "select 1\n",
+        "GO",
+        "\nselect * from [dbo].[go]\n  ",
+        "go  -- end of batch\n",
+        "select 2;",
+        "\nselect 'go';",
+        "\nselect go\n",
+        "go",
+    ]);
+    // GO is an ordinary keyword in other dialects
+    assert_eq!(parse_multi("select 1\nGO\nselect 2").len(), 1);
+}
+
+#[test]
+fn test_split_mysql_delimiter() {
+    let sql = "DELIMITER $$\nCREATE TRIGGER t BEFORE INSERT ON foo\nFOR EACH ROW BEGIN\n  SET NEW.a = 1;\n  SET NEW.b = 2;\nEND$$\nDELIMITER ;\nselect 1;\nselect 2;\n";
+    let p = Parser::with_dialect(MySqlDialect);
+    let stmts = p.parse_multi(sql);
+    let stmts = stmts.iter().map(|s| to_string(s)).collect::<Vec<_>>();
+    assert_eq!(stmts, vec![
+        "DELIMITER $$",
+        "\nCREATE TRIGGER t BEFORE INSERT ON foo\nFOR EACH ROW BEGIN\n  SET NEW.a = 1;\n  SET NEW.b = 2;\nEND$$",
+        "\nDELIMITER ;",
+        "\nselect 1;",
+        "\nselect 2;",
+    ]);
+    assert_eq!(stmts.join(""), sql.trim_end());
+}
+
+#[test]
+fn test_split_mysql_delimiter_slashes() {
+    let sql = "delimiter //\nselect 1; select '//'//\nselect 2//\ndelimiter ;\nselect delimiter from foo; select 3;";
+    let stmts = Parser::with_dialect(MySqlDialect).parse_multi(sql);
+    let stmts = stmts.iter().map(|s| to_string(s)).collect::<Vec<_>>();
+    assert_eq!(stmts, vec![
+        "delimiter //",
+        "\nselect 1; select '//'//",
+        "\nselect 2//",
+        "\ndelimiter ;",
+        "\nselect delimiter from foo; ",
+        "select 3;",
+    ]);
+}
+
+fn texts(stmts: Vec<StatementSlice<'_>>) -> Vec<&str> {
+    stmts.iter().map(|stmt| stmt.text).collect()
+}
+
+#[test]
+fn test_split_delimiter_word() {
+    // DELIMITER is only a client command of MySQL
+    let sql = "COPY t FROM '/x.csv' WITH (\n  FORMAT csv,\n  DELIMITER ','\n);\nSELECT 1;\nSELECT 2;";
+    let expected = ["COPY t FROM '/x.csv' WITH (\n  FORMAT csv,\n  DELIMITER ','\n);", "SELECT 1;", "SELECT 2;"];
+    assert_eq!(texts(split(sql)), expected);
+    assert_eq!(parse_multi(sql).len(), 3);
+    let sql = "SELECT a,\ndelimiter FROM t;\nSELECT 1;";
+    let expected = ["SELECT a,\ndelimiter FROM t;", "SELECT 1;"];
+    assert_eq!(texts(split(sql)), expected);
+    assert_eq!(parse_multi(sql).len(), 2);
+
+    // the command fills its line at the start of a statement outside any parenthesis
+    let p = Parser::with_dialect(MySqlDialect);
+    assert_eq!(texts(p.split("SELECT a,\ndelimiter FROM t;\nSELECT 1;")), ["SELECT a,\ndelimiter FROM t;", "SELECT 1;"]);
+    assert_eq!(texts(p.split("SELECT f(\ndelimiter //\n);\nSELECT 1;")), ["SELECT f(\ndelimiter //\n);", "SELECT 1;"]);
+    assert_eq!(texts(p.split("delimiter // x\nSELECT 1;")), ["delimiter // x\nSELECT 1;"]);
+    assert_eq!(texts(p.split("SELECT 1;\ndelimiter //\nSELECT 2//")), ["SELECT 1;", "delimiter //", "SELECT 2//"]);
+    assert_stream(MySqlDialect, "SELECT a,\ndelimiter FROM t;\nSELECT (\ndelimiter //\n);\ndelimiter //\nSELECT 2//\n");
+}
+
+#[test]
+fn test_split_oracle_plsql_blocks() {
+    let sql = "CREATE OR REPLACE PACKAGE BODY pkg AS
+  PROCEDURE a IS
+  BEGIN
+    IF x > 1 THEN
+      UPDATE t SET c = 1;
+    END IF;
+  END a;
+  FUNCTION b RETURN NUMBER IS
+  BEGIN
+    RETURN 1;
+  END b;
+END pkg;
+/
+DECLARE
+  n NUMBER := 10 / 2;
+BEGIN
+  BEGIN
+    NULL;
+  END;
+END;
+/
+select * from dual;
+select 1 from dual
+/
+";
+    let p = Parser::with_dialect(OracleDialect);
+    let stmts = p.parse_multi(sql);
+    assert_eq!(stmts.len(), 4);
+    let stmts = stmts.iter().map(|s| to_string(s)).collect::<Vec<_>>();
+    assert!(stmts[0].starts_with("CREATE OR REPLACE PACKAGE BODY") && stmts[0].ends_with("END pkg;\n/"));
+    assert!(stmts[1].starts_with("\nDECLARE") && stmts[1].ends_with("END;\n/"));
+    assert_eq!(stmts[2], "\nselect * from dual;");
+    assert_eq!(stmts[3], "\nselect 1 from dual\n/");
+    assert_eq!(stmts.join(""), sql.trim_end());
+}
+
+#[test]
+fn test_split_oracle_create_trigger() {
+    let sql = "create trigger trg before insert on t for each row\nbegin\n  :new.id := seq.nextval;\nend;\n/\ncreate table t2 (id number);";
+    let p = Parser::with_dialect(OracleDialect);
+    let stmts = p.parse_multi(sql);
+    let stmts = stmts.iter().map(|s| to_string(s)).collect::<Vec<_>>();
+    assert_eq!(stmts, vec![
+        "create trigger trg before insert on t for each row\nbegin\n  :new.id := seq.nextval;\nend;\n/",
+        "\ncreate table t2 (id number);",
+    ]);
+}
+
+// statements streamed with the buffer lexed again after every line are the statements of the whole script
+fn assert_stream<D: Dialect + Clone + 'static>(dialect: D, sql: &str) {
+    let p = Parser::with_dialect(dialect);
+    let expected = p.parse_multi(sql);
+    let reader = BufReader::with_capacity(4, sql.as_bytes());
+    let stmts = p.parse_stream(reader).with_chunk_size(1).collect::<std::io::Result<Vec<_>>>().unwrap();
+    assert_eq!(stmts, expected);
+}
+
+#[test]
+fn test_split_stream() {
+    assert_stream(GenericDialect, "select 1;\nselect 'a;\n;b' from t; select 2\n;\n\nselect 3");
+    assert_stream(GenericDialect, "select 1;\nselect 'a;\nb;\nc;\nd;\ne;\nf;\ng' from t;\nselect 2;");
+    assert_stream(GenericDialect, "select 1;\nselect /* a;\nb;\nc;\nd;\ne;\nf;\ng */ 1;\nselect 2;");
+    assert_stream(PostgreSqlDialect, "select 1;\nselect $$ a;\nb;\nc;\nd;\ne;\nf;\ng $$;\nselect 2;");
+    assert_stream(MsSqlDialect, "select 1;\nselect [a;\nb;\nc;\nd;\ne;\nf;\ng] from t;\nselect 2;");
+    assert_stream(GenericDialect, "select /* a;\n b; */ 1;\nselect \"x;\ny\" from t;\n-- c;\nselect 2;");
+    assert_stream(GenericDialect, "select /* a /* b;\n */ c;\n */ 1;\nselect 2;");
+    assert_stream(PostgreSqlDialect, "create function f() returns int as $$\nselect 1;\n$$ language sql;\nselect $tag$ a;\n$tag$;\nselect E'a\\';\n';");
+    assert_stream(BigQueryDialect, "select '''a;\n'b;\n''';\nselect r'x;\ny';");
+    assert_stream(MsSqlDialect, "select [a;\nb] from t\nGO\nselect 1; GO\nselect 2\ngo\n");
+    assert_stream(MySqlDialect, "DELIMITER //\ncreate procedure p()\nbegin\n  select 1;\nend//\nDELIMITER ;\nselect 2;\n");
+    assert_stream(OracleDialect, "begin\n  null;\nend;\n/\nselect 1 from dual;\ncreate procedure p is\nbegin\n  null;\nend;\n/\n");
+}
+
+#[test]
+fn test_split_stream_large() {
+    let sql = (0..2000).map(|i| format!("insert into t values ({}, 'line\n{};');\n", i, i)).collect::<String>();
+    let p = Parser::default();
+    let stmts = p.parse_stream_no_grouping(sql.as_bytes()).with_chunk_size(64).collect::<std::io::Result<Vec<_>>>().unwrap();
+    assert_eq!(stmts, p.parse_multi_no_grouping(&sql));
+    assert_eq!(stmts.len(), 2000);
+    assert_eq!(stmts[1999].iter().find(|t| !t.is_whitespace()).unwrap().span.line, 3999);
+}
+
+#[test]
+fn test_split_stream_invalid_utf8() {
+    let p = Parser::default();
+    let bytes: &[u8] = b"select 1;\nselect 2;\nselect '\xff';\n";
+    let mut stmts = p.parse_stream(bytes).with_chunk_size(1);
+    assert_eq!(to_string(&stmts.next().unwrap().unwrap()), "select 1;");
+    assert!(stmts.next().unwrap().is_err());
+    assert!(stmts.next().is_none());
+}
+
+// reader counting the bytes read from it
+struct CountingReader<'s> {
+    sql: &'s [u8],
+    read: std::rc::Rc<std::cell::Cell<usize>>,
+}
+
+impl std::io::Read for CountingReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.sql.read(buf)?;
+        self.read.set(self.read.get() + n);
+        Ok(n)
+    }
+}
+
+#[test]
+fn test_split_stream_error_token() {
+    // an unknown character is not an unclosed quote, the statements after it are yielded as they are read
+    let sql = "select € 1;\n".to_string() + &"select 2;\n".repeat(50000);
+    let read = std::rc::Rc::new(std::cell::Cell::new(0));
+    let reader = BufReader::with_capacity(64, CountingReader { sql: sql.as_bytes(), read: read.clone() });
+    let p = Parser::default();
+    let mut stmts = p.parse_stream_no_grouping(reader).with_chunk_size(64);
+    assert_eq!(to_string(&stmts.next().unwrap().unwrap()), "select € 1;");
+    assert_eq!(to_string(&stmts.next().unwrap().unwrap()), "\nselect 2;");
+    assert!(read.get() < 1024);
+    assert_eq!(stmts.count(), 49999);
+}
+
+#[test]
+fn test_split_slices() {
+    let sql = "  select 1; -- one\nselect 'a;\nb'  \n;\n\nselect 3\n";
+    let stmts = split(sql);
+    assert_eq!(stmts.len(), 3);
+    assert_eq!(stmts[0].text, "select 1; -- one");
+    assert_eq!(stmts[0].range, 2..18);
+    assert_eq!(stmts[0].lines, 1..=1);
+    assert_eq!(stmts[0].terminator, Some(";"));
+    assert_eq!(stmts[1].text, "select 'a;\nb'  \n;");
+    assert_eq!(stmts[1].lines, 2..=4);
+    assert_eq!(stmts[2].text, "select 3");
+    assert_eq!(stmts[2].lines, 6..=6);
+    assert_eq!(stmts[2].terminator, None);
+    for stmt in &stmts {
+        assert_eq!(&sql[stmt.range.clone()], stmt.text);
+    }
+    let texts = parse_multi(sql).iter().map(|stmt| to_string(stmt).trim().to_string()).collect::<Vec<_>>();
+    assert_eq!(stmts.iter().map(|stmt| stmt.text).collect::<Vec<_>>(), texts);
+}
+
+#[test]
+fn test_split_slices_dialects() {
+    let p = Parser::with_dialect(MsSqlDialect);
+    let stmts = p.split("select 1\nGO\nselect 2;\n");
+    // the batch separator is the terminator of its own statement, not of the statement before it
+    assert_eq!(stmts.iter().map(|stmt| (stmt.text, stmt.terminator)).collect::<Vec<_>>(),
+        vec![("select 1", None), ("GO", Some("GO")), ("select 2;", Some(";"))]);
+    let stmts = p.split("select 1;\nselect 2\n  go -- end");
+    assert_eq!(stmts.iter().map(|stmt| (stmt.text, stmt.terminator)).collect::<Vec<_>>(),
+        vec![("select 1;", Some(";")), ("select 2", None), ("go -- end", Some("go"))]);
+
+    let p = Parser::with_dialect(MySqlDialect);
+    let stmts = p.split("DELIMITER //\nselect 1//\nDELIMITER ;\n");
+    assert_eq!(stmts.iter().map(|stmt| (stmt.text, stmt.terminator)).collect::<Vec<_>>(),
+        vec![("DELIMITER //", None), ("select 1//", Some("//")), ("DELIMITER ;", None)]);
+
+    let p = Parser::with_dialect(OracleDialect);
+    let stmts = p.split("begin\n  null;\nend;\n/\nselect 1 from dual;");
+    assert_eq!(stmts[0].text, "begin\n  null;\nend;\n/");
+    assert_eq!(stmts[0].terminator, Some("/"));
+    assert_eq!(stmts[0].lines, 1..=4);
+}
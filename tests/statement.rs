@@ -0,0 +1,260 @@
+use sqlparse::{
+    Parser, Statement, StatementType, TableRef, TableAccess, ColumnLineage, ColumnRef, Expression, BinaryOperator, TokenType,
+    MsSqlDialect, OracleDialect, parse_multi,
+};
+
+fn statement_type(sql: &str) -> StatementType {
+    Statement::from(parse_multi(sql).remove(0)).statement_type()
+}
+
+#[test]
+fn test_statement_type() {
+    let sqls = vec![
+        ("select * from foo", StatementType::Select),
+        ("SELECT 1; select 2", StatementType::Select),
+        ("insert into foo values (1)", StatementType::Insert),
+        ("replace into foo values (1)", StatementType::Insert),
+        ("update foo set a = 1", StatementType::Update),
+        ("delete from foo", StatementType::Delete),
+        ("merge into t using s on t.a = s.a when matched then delete", StatementType::Merge),
+        ("create table foo (a int)", StatementType::Create),
+        ("create or replace view v as select 1", StatementType::Create),
+        ("alter table foo add b int", StatementType::Alter),
+        ("drop table foo", StatementType::Drop),
+        ("set search_path = public", StatementType::Set),
+        ("foo bar", StatementType::Unknown),
+    ];
+    for (sql, typ) in sqls {
+        assert_eq!(statement_type(sql), typ, "{}", sql);
+    }
+}
+
+#[test]
+fn test_statement_type_transaction() {
+    let sqls = vec!["begin;", "begin transaction;", "start transaction", "commit", "rollback to savepoint a",
+        "savepoint a", "release savepoint a", "end", "abort"];
+    for sql in sqls {
+        assert_eq!(statement_type(sql), StatementType::Transaction, "{}", sql);
+    }
+    let p = Parser::with_dialect(MsSqlDialect);
+    assert_eq!(p.parse_statements("BEGIN TRAN").remove(0).statement_type(), StatementType::Transaction);
+    // a block is not a transaction
+    let p = Parser::with_dialect(OracleDialect);
+    assert_eq!(p.parse_statements("BEGIN\n null;\nEND;\n/").remove(0).statement_type(), StatementType::Unknown);
+}
+
+#[test]
+fn test_statement_type_comments_and_parenthesis() {
+    assert_eq!(statement_type("/* c */ -- x\n select 1"), StatementType::Select);
+    assert_eq!(statement_type("\n\n  -- x\n  insert into foo values (1)"), StatementType::Insert);
+    assert_eq!(statement_type("(select 1) union select 2"), StatementType::Select);
+    assert_eq!(statement_type("-- only a comment"), StatementType::Unknown);
+    assert_eq!(Statement::new(vec![]).statement_type(), StatementType::Unknown);
+}
+
+#[test]
+fn test_statement_type_cte() {
+    assert_eq!(statement_type("with a as (select 1), b as (select 2) select * from a"), StatementType::Select);
+    assert_eq!(statement_type("with recursive a(x) as (select 1) insert into t select * from a"), StatementType::Insert);
+    assert_eq!(statement_type("WITH a AS MATERIALIZED (select 1) update t set x = 1"), StatementType::Update);
+    assert_eq!(statement_type("with a as (insert into t values (1) returning *) delete from u"), StatementType::Delete);
+    assert_eq!(statement_type("with a as (select 1)"), StatementType::Unknown);
+}
+
+#[test]
+fn test_parse_statements() {
+    let p = Parser::default();
+    let stmts = p.parse_statements("select 1; delete from foo;");
+    assert_eq!(stmts.len(), 2);
+    assert_eq!(stmts[1].statement_type().to_string(), "DELETE");
+    assert_eq!(stmts[1].to_string(), "delete from foo;");
+    assert_eq!(stmts[1].first_token().unwrap().value(), "delete");
+}
+
+fn tables(sql: &str) -> Vec<(Option<String>, String, Option<String>, TableAccess)> {
+    Statement::from(parse_multi(sql).remove(0)).tables().into_iter()
+        .map(|TableRef { schema, name, alias, access }| (schema, name, alias, access))
+        .collect()
+}
+
+fn table(schema: Option<&str>, name: &str, alias: Option<&str>, access: TableAccess) -> (Option<String>, String, Option<String>, TableAccess) {
+    (schema.map(String::from), name.to_string(), alias.map(String::from), access)
+}
+
+#[test]
+fn test_tables_select() {
+    use TableAccess::Read;
+    let sql = "select a.x, b.y from s.foo as a join bar b on a.id = b.id left join (select * from baz) z on 1=1 where a.c in (select c from qux)";
+    assert_eq!(tables(sql), vec![
+        table(Some("s"), "foo", Some("a"), Read),
+        table(None, "bar", Some("b"), Read),
+        table(None, "baz", None, Read),
+        table(None, "qux", None, Read),
+    ]);
+    assert_eq!(tables("select * from `db`.`t` x, \"s\".\"t\", a.b.c"), vec![
+        table(Some("db"), "t", Some("x"), Read),
+        table(Some("s"), "t", None, Read),
+        table(Some("a.b"), "c", None, Read),
+    ]);
+    assert_eq!(tables("select * from a natural join b using (x)"), vec![table(None, "a", None, Read), table(None, "b", None, Read)]);
+    // a table function is not a table
+    assert_eq!(tables("select * from generate_series(1, 2) g"), vec![]);
+    assert_eq!(tables("select (select max(x) from t) as m from u where exists (select 1 from v)"),
+        vec![table(None, "t", None, Read), table(None, "u", None, Read), table(None, "v", None, Read)]);
+    // FROM in function arguments and in IS DISTINCT FROM is not followed by a table
+    assert_eq!(tables("select extract(year from d) from t"), vec![table(None, "t", None, Read)]);
+    assert_eq!(tables("select trim(both ' ' from name) from t"), vec![table(None, "t", None, Read)]);
+    assert_eq!(tables("select * from t where a is distinct from b"), vec![table(None, "t", None, Read)]);
+    assert_eq!(tables("select coalesce((select max(x) from u), 0) from t"), vec![table(None, "u", None, Read), table(None, "t", None, Read)]);
+}
+
+#[test]
+fn test_tables_write() {
+    use TableAccess::{Read, Write};
+    assert_eq!(tables("insert into s.t (a, b) select x, y from src"), vec![table(Some("s"), "t", None, Write), table(None, "src", None, Read)]);
+    assert_eq!(tables("insert into t (a, b) values (1, 2)"), vec![table(None, "t", None, Write)]);
+    assert_eq!(tables("update t u set a = 1 from o where u.id = o.id"), vec![table(None, "t", Some("u"), Write), table(None, "o", None, Read)]);
+    assert_eq!(tables("delete from t where id in (select id from x)"), vec![table(None, "t", None, Write), table(None, "x", None, Read)]);
+    assert_eq!(tables("delete from t using u where t.a = u.a"), vec![table(None, "t", None, Write), table(None, "u", None, Read)]);
+    assert_eq!(tables("merge into t using s on t.a = s.a when matched then delete"), vec![table(None, "t", None, Write), table(None, "s", None, Read)]);
+    assert_eq!(tables("select * into new_t from t"), vec![table(None, "new_t", None, Write), table(None, "t", None, Read)]);
+    assert_eq!(tables("create table if not exists s.t (a int)"), vec![table(Some("s"), "t", None, Write)]);
+    assert_eq!(tables("create table t2 as select * from t1"), vec![table(None, "t2", None, Write), table(None, "t1", None, Read)]);
+    assert_eq!(tables("create or replace view v as select * from t"), vec![table(None, "v", None, Write), table(None, "t", None, Read)]);
+    assert_eq!(tables("drop table if exists a, b"), vec![table(None, "a", None, Write), table(None, "b", None, Write)]);
+    assert_eq!(tables("alter table t add c int"), vec![table(None, "t", None, Write)]);
+    assert_eq!(tables("truncate table t"), vec![table(None, "t", None, Write)]);
+}
+
+#[test]
+fn test_tables_cte() {
+    use TableAccess::{Read, Write};
+    assert_eq!(tables("with c as (select * from real_t) select * from c join other on 1=1"),
+        vec![table(None, "real_t", None, Read), table(None, "other", None, Read)]);
+    assert_eq!(tables("with recursive r(n) as (select 1 union all select n+1 from r) insert into t select * from r"),
+        vec![table(None, "t", None, Write)]);
+    assert_eq!(tables("with a as (select * from x), b as (select * from a join y on 1=1) select * from b, s.a"),
+        vec![table(None, "x", None, Read), table(None, "y", None, Read), table(Some("s"), "a", None, Read)]);
+    // a common table expression is only visible in its own query level, and in its own query with RECURSIVE
+    assert_eq!(tables("with t as (select * from t) select * from t"), vec![table(None, "t", None, Read)]);
+    assert_eq!(tables("select * from (with c as (select 1) select * from c) x join (select * from c) y on 1=1"),
+        vec![table(None, "c", None, Read)]);
+    assert_eq!(tables("with recursive t as (select * from t) select * from t"), vec![]);
+    assert_eq!(tables("select * from `db`.`t` as `x`"), vec![table(Some("db"), "t", Some("x"), Read)]);
+}
+
+fn lineage(sql: &str) -> Vec<ColumnLineage> {
+    Statement::from(parse_multi(sql).remove(0)).column_lineage()
+}
+
+// output column names with their sources as `table.column`
+fn sources(sql: &str) -> Vec<(String, Vec<String>)> {
+    lineage(sql).into_iter().map(|c| (c.name, c.sources.iter().map(|s| s.to_string()).collect())).collect()
+}
+
+fn column(name: &str, sources: &[&str]) -> (String, Vec<String>) {
+    (name.to_string(), sources.iter().map(|s| s.to_string()).collect())
+}
+
+#[test]
+fn test_column_lineage_select() {
+    assert_eq!(sources("select a.x, b.y as yy, count(*) c, a.x + b.z total from s.a join b on a.id = b.id"), vec![
+        column("x", &["s.a.x"]), column("yy", &["b.y"]), column("c", &[]), column("total", &["s.a.x", "b.z"]),
+    ]);
+    assert_eq!(sources("select distinct a, case when b > 0 then c else d end as e, 1 one from t"), vec![
+        column("a", &["t.a"]), column("e", &["t.b", "t.c", "t.d"]), column("one", &[]),
+    ]);
+    assert_eq!(sources("select x.a, y.b from t1 x left join t2 y on x.id = y.id"), vec![
+        column("a", &["t1.a"]), column("b", &["t2.b"]),
+    ]);
+    assert_eq!(sources("select a, b from t1, t2"), vec![column("a", &["a"]), column("b", &["b"])]);
+    assert_eq!(sources("select *, t.* from t"), vec![column("*", &["t.*"]), column("*", &["t.*"])]);
+    assert_eq!(sources("select id from a union all select id2 from b"), vec![column("id", &["a.id", "b.id2"])]);
+    assert!(lineage("update t set a = 1").is_empty());
+}
+
+#[test]
+fn test_column_lineage_subquery_and_cte() {
+    assert_eq!(sources("select d.total from (select sum(amount) total from orders) d"), vec![column("total", &["orders.amount"])]);
+    assert_eq!(sources("select (select max(z) from w) m from t"), vec![column("m", &["w.z"])]);
+    assert_eq!(sources("select * from (select a k, b from u) q"), vec![column("k", &["u.a"]), column("b", &["u.b"])]);
+    assert_eq!(sources("with c as (select id, name n from users u) select c.n, id from c"), vec![
+        column("n", &["users.name"]), column("id", &["users.id"]),
+    ]);
+    assert_eq!(sources("with c(p, q) as (select x, y + 1 from t) select q from c"), vec![column("q", &["t.y"])]);
+}
+
+#[test]
+fn test_column_lineage_insert() {
+    let columns = lineage("insert into s.t (a, b) select x, upper(y) from src");
+    let target = |column: &str| Some(ColumnRef { table: Some("s.t".to_string()), column: column.to_string() });
+    assert_eq!(columns.iter().map(|c| c.target.clone()).collect::<Vec<_>>(), vec![target("a"), target("b")]);
+    assert_eq!(columns.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), vec!["a", "b"]);
+    assert_eq!(columns[1].sources, vec![ColumnRef { table: Some("src".to_string()), column: "y".to_string() }]);
+    assert_eq!(sources("with c as (select x from t) insert into o select x n from c"), vec![column("n", &["t.x"])]);
+    assert_eq!(lineage("insert into o select x from t")[0].target.as_ref().map(|t| t.to_string()), Some("o.x".to_string()));
+    assert!(lineage("insert into t (a) values (1)").is_empty());
+}
+
+// condition of the WHERE, HAVING or JOIN clause with every operation in parenthesis
+fn condition(sql: &str, typ: TokenType) -> Option<String> {
+    let tokens = parse_multi(sql).remove(0);
+    let clause = tokens.iter().find(|t| t.typ == typ)?;
+    Expression::from_clause(clause).map(|e| e.to_string())
+}
+
+fn where_condition(sql: &str) -> Option<String> {
+    condition(&format!("select * from t where {}", sql), TokenType::Where)
+}
+
+#[test]
+fn test_expression_precedence() {
+    assert_eq!(where_condition("a or b and not c").unwrap(), "(a OR (b AND (NOT c)))");
+    assert_eq!(where_condition("(a or b) and c").unwrap(), "((a OR b) AND c)");
+    assert_eq!(where_condition("not a = 1 + 2 * -c").unwrap(), "(NOT (a = (1 + (2 * (-c)))))");
+    assert_eq!(where_condition("y = (1 + 2) * 3 / 4 % 5 - 6").unwrap(), "(y = (((((1 + 2) * 3) / 4) % 5) - 6))");
+    assert_eq!(where_condition("a - 1 > 0 and x = 2 - 3 * b").unwrap(), "(((a - 1) > 0) AND (x = (2 - (3 * b))))");
+    assert_eq!(where_condition("a - b > -1").unwrap(), "((a - b) > -1)");
+    assert_eq!(where_condition("a.b::int >= f(x) || 'y' and b <> 'x'").unwrap(), "((a.b::int >= (f(x) || 'y')) AND (b <> 'x'))");
+
+    let tokens = parse_multi("select * from t where a = 1 and b = 2").remove(0);
+    let expr = Expression::from_clause(tokens.iter().find(|t| t.typ == TokenType::Where).unwrap()).unwrap();
+    match expr {
+        Expression::Binary { left, op: BinaryOperator::And, right } => {
+            assert!(matches!(*left, Expression::Binary { op: BinaryOperator::Eq, .. }));
+            assert!(matches!(*right, Expression::Binary { op: BinaryOperator::Eq, .. }));
+        },
+        _ => panic!("{:?}", expr),
+    }
+    assert!(where_condition("a = 1 and").is_none());
+
+    // the sign of a number after an operand is a binary minus
+    let tokens = parse_multi("select * from t where a - 1").remove(0);
+    let expr = Expression::from_clause(tokens.iter().find(|t| t.typ == TokenType::Where).unwrap()).unwrap();
+    match expr {
+        Expression::Binary { op: BinaryOperator::Minus, right, .. } => {
+            assert!(matches!(*right, Expression::UnsignedNumber { token, text: "1" } if token.value() == "- 1"));
+        },
+        _ => panic!("{:?}", expr),
+    }
+}
+
+#[test]
+fn test_expression_predicates() {
+    assert_eq!(where_condition("b between 1 and 2 and i not between 3 and 4 + 1").unwrap(),
+        "((b BETWEEN 1 AND 2) AND (i NOT BETWEEN 3 AND (4 + 1)))");
+    assert_eq!(where_condition("c is not null or c is null or c is not distinct from d or c is true").unwrap(),
+        "((((c IS NOT NULL) OR (c IS NULL)) OR (c IS NOT DISTINCT FROM d)) OR (c IS true))");
+    assert_eq!(where_condition("d not in (1, 2+3, f(x)) and e in (select 1)").unwrap(),
+        "((d NOT IN (1, (2 + 3), f(x))) AND (e IN (select 1)))");
+    assert_eq!(where_condition("not exists (select 1) and exists (select 2)").unwrap(),
+        "((NOT EXISTS (select 1)) AND (EXISTS (select 2)))");
+    assert_eq!(where_condition("e like 'x%' escape '!' and g not ilike 'a' || 'b'").unwrap(),
+        "((e LIKE 'x%' ESCAPE '!') AND (g NOT ILIKE ('a' || 'b')))");
+
+    assert_eq!(condition("select a from t group by a having count(*) > 1 and sum(x) < 10", TokenType::Having).unwrap(),
+        "((count(*) > 1) AND (sum(x) < 10))");
+    assert_eq!(condition("select * from a join b on a.x = b.x and a.y > 1", TokenType::Join).unwrap(),
+        "((a.x = b.x) AND (a.y > 1))");
+    assert!(condition("select * from a join b using (x)", TokenType::Join).is_none());
+}
@@ -1,4 +1,4 @@
-use sqlparse::{TokenType, parse_no_grouping, parse, group_tokenlist};
+use sqlparse::{Token, TokenType, Span, LexError, Parser, MySqlDialect, parse_no_grouping, parse, group_tokenlist};
 
 #[test]
 fn test_tokenize_simple() {
@@ -7,7 +7,7 @@ fn test_tokenize_simple() {
     assert_eq!(tokens.len(), 8);
     assert_eq!(tokens[0].typ, TokenType::KeywordDML);
     assert_eq!(tokens[7].typ, TokenType::Punctuation);
-    assert_eq!(tokens[7].value, ";");
+    assert_eq!(tokens[7].value(), ";");
 }
 
 #[test]
@@ -16,7 +16,7 @@ fn test_tokenize_backticks() {
     let tokens = parse_no_grouping(sql);
     assert_eq!(tokens.len(), 3);
     assert_eq!(tokens[0].typ, TokenType::Name);
-    assert_eq!(tokens[0].value, "`foo`");
+    assert_eq!(tokens[0].value(), "`foo`");
 }
 
 #[test]
@@ -24,7 +24,7 @@ fn test_tokenize_linebreaks() {
     let sqls = vec!["foo\nbar\n", "foo\rbar\r", "foo\r\nbar\r\n", "foo\r\nbar\n"];
     for sql in sqls {
         let tokens = parse_no_grouping(sql);
-        let s = tokens.iter().map(|t| t.value.clone()).collect::<Vec<_>>().join("");
+        let s = tokens.iter().map(|t| t.value()).collect::<Vec<_>>().join("");
         assert_eq!(sql, s);
     }
 }
@@ -37,7 +37,7 @@ fn test_tokenize_inline_keywords() {
     assert_eq!(tokens.len(), 3);
     assert_eq!(tokens[0].typ, TokenType::KeywordDDL);
     assert_eq!(tokens[2].typ, TokenType::Name);
-    assert_eq!(tokens[2].value, "created_foo");
+    assert_eq!(tokens[2].value(), "created_foo");
     
     let sql = "enddate";
     let tokens = parse_no_grouping(sql);
@@ -53,7 +53,7 @@ fn test_tokenize_inline_keywords() {
     let tokens = parse_no_grouping(sql);
     assert_eq!(tokens.len(), 3);
     assert_eq!(tokens[2].typ, TokenType::Name);
-    assert_eq!(tokens[2].value, "join_col");
+    assert_eq!(tokens[2].value(), "join_col");
 }
 
 #[test]
@@ -62,7 +62,7 @@ fn test_tokenize_negative_numbers() {
     let tokens = parse_no_grouping(sql);
     assert_eq!(tokens.len(), 4);
     assert_eq!(tokens[2].typ, TokenType::NumberInteger);
-    assert_eq!(tokens[2].value, "-1");
+    assert_eq!(tokens[2].value(), "-1");
 }
 
 #[test]
@@ -108,4 +108,164 @@ fn test_parse_endifloop() {
         assert_eq!(tokens.len(), 1);
         assert_eq!(tokens[0].typ, TokenType::Keyword);
     }
-}
\ No newline at end of file
+}
+#[test]
+fn test_tokenize_span() {
+    let sql = "select *\nfrom foo;";
+    let tokens = parse_no_grouping(sql);
+    assert_eq!(tokens[0].span, Span::new(0, 6, 1, 1));
+    assert_eq!(tokens[2].span, Span::new(7, 8, 1, 8));
+    assert_eq!(tokens[4].span, Span::new(9, 13, 2, 1));
+    assert_eq!(tokens[6].span, Span::new(14, 17, 2, 6));
+    for token in &tokens {
+        assert_eq!(&sql[token.span.start..token.span.end], token.value());
+    }
+}
+
+#[test]
+fn test_tokenize_span_crlf_multibyte() {
+    let sql = "select 'ü'\r\n  , bar";
+    let tokens = parse_no_grouping(sql);
+    assert_eq!(tokens[2].span, Span::new(7, 11, 1, 8));
+    assert_eq!(tokens[3].typ, TokenType::Newline);
+    let bar = tokens.last().unwrap();
+    assert_eq!(bar.value(), "bar");
+    assert_eq!(bar.span, Span::new(17, 20, 2, 5));
+}
+
+#[test]
+fn test_tokenize_error_token() {
+    let sql = "select a € b;";
+    let tokens = parse_no_grouping(sql);
+    let s = tokens.iter().map(|t| t.value()).collect::<String>();
+    assert_eq!(s, sql);
+    let error = tokens.iter().find(|t| t.typ == TokenType::Error).unwrap();
+    assert_eq!(error.value(), "€");
+    assert_eq!(error.span, Span::new(9, 12, 1, 10));
+    assert_eq!(tokens.last().unwrap().value(), ";");
+}
+
+#[test]
+fn test_try_parse_error() {
+    let p = Parser::default();
+    let err = p.try_parse("select 1;\nselect a € b").unwrap_err();
+    assert_eq!(err, LexError { offset: 19, line: 2, column: 10, unexpected: '€' });
+    assert_eq!(err.to_string(), "unexpected character '€' at line 2, column 10 (offset 19)");
+    let err = p.try_parse_multi("select 1;\nselect a € b").unwrap_err();
+    assert_eq!(err.offset, 19);
+    let stmts = p.try_parse_multi("select 1; select 2").unwrap();
+    assert_eq!(stmts.len(), 2);
+}
+
+#[test]
+fn test_tokenize_dollar_quoted() {
+    let sqls = vec![
+        ("$$foo; bar$$", "$$foo; bar$$"),
+        ("$body$ a $$ b $body$ c", "$body$ a $$ b $body$"),
+        ("$_1$$_1$", "$_1$$_1$"),
+    ];
+    for (sql, value) in sqls {
+        let tokens = parse_no_grouping(sql);
+        assert_eq!(tokens[0].typ, TokenType::StringDollar);
+        assert_eq!(tokens[0].value(), value);
+    }
+    // unterminated tag and numbered placeholders are not strings
+    let tokens = parse_no_grouping("select $1, $2");
+    assert_eq!(tokens[2].typ, TokenType::NamePlaceholder);
+    let tokens = Parser::with_dialect(MySqlDialect).parse_no_grouping("select $$a$$");
+    assert!(tokens.iter().all(|t| t.typ != TokenType::StringDollar));
+}
+
+#[test]
+fn test_tokenize_nested_comment() {
+    let sql = "select /* outer /* inner */ still comment */ 1";
+    let tokens = parse_no_grouping(sql);
+    assert_eq!(tokens.len(), 5);
+    assert_eq!(tokens[2].typ, TokenType::CommentMultiline);
+    assert_eq!(tokens[2].value(), "/* outer /* inner */ still comment */");
+    // an unbalanced comment ends at the first close
+    let tokens = parse_no_grouping("/* a /* b */ 1");
+    assert_eq!(tokens[0].value(), "/* a /* b */");
+    // an unclosed comment is an operator and a wildcard
+    let tokens = parse_no_grouping("select */ /*x /*x");
+    assert_eq!(tokens.iter().map(|t| t.typ.clone()).collect::<Vec<_>>(), [
+        TokenType::KeywordDML, TokenType::Whitespace, TokenType::Wildcard, TokenType::Operator, TokenType::Whitespace,
+        TokenType::Operator, TokenType::Wildcard, TokenType::Name, TokenType::Whitespace, TokenType::Operator, TokenType::Wildcard, TokenType::Name,
+    ]);
+}
+
+#[test]
+fn test_tokenize_prefixed_strings() {
+    let sql = r"select E'it\'s', N'caf''e', X'1F', B'0101', U&'d\0061t'";
+    let tokens = parse_no_grouping(sql);
+    let strings = tokens.iter().filter(|t| t.typ == TokenType::StringSingle).map(|t| t.value()).collect::<Vec<_>>();
+    assert_eq!(strings, vec![r"E'it\'s'", "N'caf''e'", "X'1F'", "B'0101'", r"U&'d\0061t'"]);
+    assert_eq!(tokens.len(), 15);
+}
+
+#[test]
+fn test_tokenize_unicode_identifiers() {
+    let sql = "select имя, über_größe, αβγ.δ from таблица where 名前 = 1";
+    let tokens = parse_no_grouping(sql);
+    let names = tokens.iter().filter(|t| t.typ == TokenType::Name).map(|t| t.value()).collect::<Vec<_>>();
+    assert_eq!(names, vec!["имя", "über_größe", "αβγ", "δ", "таблица", "名前"]);
+    assert!(tokens.iter().all(|t| t.typ != TokenType::Error));
+    // keywords end at any non identifier character
+    let tokens = parse_no_grouping("select*from t");
+    assert_eq!(tokens[0].value(), "select");
+    assert_eq!(tokens[2].value(), "from");
+}
+
+#[test]
+fn test_tokenize_identifier_chars() {
+    let sql = "select a$b, c#d from t";
+    let tokens = parse_no_grouping(sql);
+    assert_eq!(tokens[2].value(), "a$b");
+    assert_eq!(tokens[5].value(), "c#d");
+    let tokens = Parser::with_dialect(MySqlDialect).parse_no_grouping(sql);
+    assert_eq!(tokens[2].value(), "a$b");
+    assert_eq!(tokens[5].value(), "c");
+}
+
+#[test]
+fn test_tokenize_leading_dot() {
+    let tokens = parse_no_grouping(".abc");
+    assert_eq!(tokens.len(), 2);
+    assert_eq!(tokens[0].value(), ".");
+    assert_eq!(tokens[1].value(), "abc");
+    let tokens = parse_no_grouping(".é");
+    assert_eq!(tokens[1].value(), "é");
+}
+
+#[test]
+fn test_tokenize_borrowed_values() {
+    let sql = "select a, b from foo where c = 'x'";
+    let range = sql.as_bytes().as_ptr_range();
+    let tokens = parse_no_grouping(sql);
+    assert!(tokens.iter().all(|t| range.contains(&t.value().as_ptr())));
+    // group text is joined from the children
+    let tokens = parse(sql);
+    assert_eq!(tokens[2].typ, TokenType::IdentifierList);
+    assert_eq!(tokens[2].value(), "a, b");
+    assert_eq!(tokens[0].normalized(), "SELECT");
+    // the uppercased keyword is kept for later calls
+    assert!(std::ptr::eq(tokens[0].normalized(), tokens[0].normalized()));
+    let mut token = tokens[0].clone();
+    token.set_value("update");
+    assert_eq!(token.normalized(), "UPDATE");
+    // only keyword groups have a normalized text
+    assert_eq!(tokens[2].normalized(), "");
+    let group = Token::new_parent(TokenType::KeywordOrder, vec![Token::new(TokenType::Keyword, "order"), Token::new(TokenType::Whitespace, "  "), Token::new(TokenType::Keyword, "by")]);
+    assert_eq!(group.normalized(), "ORDER BY");
+    assert_eq!(group.value(), "order  by");
+}
+
+#[test]
+fn test_tokens_send_sync() {
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+    let tokens = parse("select a, b from foo");
+    assert_send_sync(&tokens);
+    // the text of a group can be joined from another thread
+    let value = std::thread::scope(|s| s.spawn(|| tokens[2].value().to_string()).join().unwrap());
+    assert_eq!(value, "a, b");
+}
@@ -0,0 +1,66 @@
+use sqlparse::{Parser, Token, TokenType, Visit, VisitContext, Visitor, VisitorMut};
+
+#[derive(Default)]
+struct Collect<'t> {
+    names: Vec<(&'t str, usize, Option<TokenType>)>,
+    stop_at: Option<&'static str>,
+}
+
+impl<'t, 'a: 't> Visitor<'t, 'a> for Collect<'t> {
+
+    fn types(&self) -> &[TokenType] {
+        &[TokenType::Name, TokenType::Where]
+    }
+
+    fn pre_visit(&mut self, token: &'t Token<'a>, ctx: &VisitContext) -> Visit {
+        if token.typ == TokenType::Where {
+            return Visit::SkipChildren
+        }
+        self.names.push((token.value(), ctx.depth(), ctx.parent().cloned()));
+        if self.stop_at == Some(token.value()) { Visit::Stop } else { Visit::Continue }
+    }
+}
+
+#[test]
+fn test_visit() {
+    let tokens = Parser::default().parse("select a, f(b) from t where c = 1");
+    let mut collect = Collect::default();
+    sqlparse::visit(&tokens, &mut collect);
+    assert_eq!(collect.names, [
+        ("a", 2, Some(TokenType::Identifier)),
+        ("f", 3, Some(TokenType::Identifier)),
+        ("b", 4, Some(TokenType::Identifier)),
+        ("t", 2, Some(TokenType::Identifier)),
+    ]);
+
+    let mut collect = Collect { stop_at: Some("f"), ..Default::default() };
+    sqlparse::visit(&tokens, &mut collect);
+    assert_eq!(collect.names.iter().map(|n| n.0).collect::<Vec<_>>(), ["a", "f"]);
+}
+
+struct Rename;
+
+impl VisitorMut for Rename {
+
+    fn types(&self) -> &[TokenType] {
+        &[TokenType::Name]
+    }
+
+    fn post_visit(&mut self, token: &mut Token<'_>, _ctx: &VisitContext) -> Visit {
+        if token.value() == "b" {
+            token.set_value("bb");
+        }
+        Visit::Continue
+    }
+}
+
+#[test]
+fn test_visit_mut() {
+    let mut tokens = Parser::default().parse("select a, f(b) from t");
+    // cache the values of the groups before the change
+    assert_eq!(tokens[2].value(), "a, f(b)");
+    sqlparse::visit_mut(&mut tokens, &mut Rename);
+    assert_eq!(tokens[2].value(), "a, f(bb)");
+    assert_eq!(tokens[2].children.tokens[3].value(), "f(bb)");
+    assert_eq!(tokens.iter().map(|t| t.value()).collect::<String>(), "select a, f(bb) from t");
+}